@@ -1,7 +1,8 @@
 use clap::{Parser, ValueEnum};
 use cryosnap_core::{
-    CjkRegion, FontSystemFallback, PngQuantPreset, PngStrip, RasterBackend, TitleAlign,
-    TitlePathStyle,
+    BackdropFit, BackdropStyle, ChromeStyle, CjkRegion, DocumentFormat, FontSystemFallback,
+    GutterAlign, LayoutChrome, LayoutDirection, PngQuantPreset, PngStrip, RasterBackend,
+    RedactStyle, TitleAlign, TitlePathStyle, WatermarkPosition,
 };
 
 #[derive(Parser, Debug)]
@@ -31,7 +32,7 @@ pub(crate) struct Args {
     #[arg(short, long)]
     pub(crate) interactive: bool,
 
-    /// Background color (e.g. #171717).
+    /// Background color (e.g. #171717, or "auto" to follow the theme).
     #[arg(short = 'b', long)]
     pub(crate) background: Option<String>,
 
@@ -63,10 +64,47 @@ pub(crate) struct Args {
     #[arg(short = 'w', long)]
     pub(crate) wrap: Option<usize>,
 
-    /// Lines to capture (start,end).
+    /// Lines to capture: `start,end` (0-based) or ranges like `1-5,40-60`, `/fn main/../^}/`.
     #[arg(long)]
     pub(crate) lines: Option<String>,
 
+    /// Marker line shown between non-contiguous line ranges.
+    #[arg(long)]
+    pub(crate) elision: Option<String>,
+
+    /// Strip indentation shared by the captured lines.
+    #[arg(
+        long = "dedent",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) dedent: Option<bool>,
+
+    /// Spaces to indent by after dedenting (implies --dedent).
+    #[arg(long = "dedent.indent")]
+    pub(crate) dedent_indent: Option<usize>,
+
+    /// Capture a single symbol (e.g. `parse_ansi`, `impl Foo`, `Foo::bar`).
+    #[arg(long)]
+    pub(crate) symbol: Option<String>,
+
+    /// Code block to extract from a Markdown/reST/notebook input (1-based).
+    #[arg(long = "block.index")]
+    pub(crate) block_index: Option<usize>,
+
+    /// Extract the code block whose info string contains this label.
+    #[arg(long = "block.label")]
+    pub(crate) block_label: Option<String>,
+
+    /// Extract a code block under the heading containing this text.
+    #[arg(long = "block.heading")]
+    pub(crate) block_heading: Option<String>,
+
+    /// Document format for block extraction (auto, markdown, rst, ipynb).
+    #[arg(long = "block.format", value_enum)]
+    pub(crate) block_format: Option<DocumentFormatArg>,
+
     /// Show window controls.
     #[arg(
         long,
@@ -76,6 +114,27 @@ pub(crate) struct Args {
     )]
     pub(crate) window: Option<bool>,
 
+    /// Window chrome style (macos, windows, gnome, minimal, none).
+    #[arg(long = "chrome.style", value_enum)]
+    pub(crate) chrome_style: Option<ChromeStyleArg>,
+
+    /// Show the title as a tab strip.
+    #[arg(
+        long = "chrome.tabs",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) chrome_tabs: Option<bool>,
+
+    /// Add a tab for another file (repeatable).
+    #[arg(long = "chrome.tab", value_name = "PATH", action = clap::ArgAction::Append)]
+    pub(crate) chrome_extra_tabs: Option<Vec<String>>,
+
+    /// Highlighted tab (0-based).
+    #[arg(long = "chrome.active-tab")]
+    pub(crate) chrome_active_tab: Option<usize>,
+
     /// Show line numbers.
     #[arg(
         long,
@@ -109,6 +168,54 @@ pub(crate) struct Args {
     #[arg(long = "shadow.y")]
     pub(crate) shadow_y: Option<f32>,
 
+    /// Backdrop behind the window (none, solid, linear, radial, image, dots, grid, noise).
+    #[arg(long = "backdrop.style", value_enum)]
+    pub(crate) backdrop_style: Option<BackdropStyleArg>,
+
+    /// Backdrop colors (comma-separated gradient stops, or fill then pattern ink).
+    #[arg(long = "backdrop.colors")]
+    pub(crate) backdrop_colors: Option<String>,
+
+    /// Linear gradient angle in degrees.
+    #[arg(long = "backdrop.angle")]
+    pub(crate) backdrop_angle: Option<f32>,
+
+    /// Backdrop image (PNG or JPEG).
+    #[arg(long = "backdrop.image")]
+    pub(crate) backdrop_image: Option<String>,
+
+    /// How the backdrop image fills the canvas (cover, contain).
+    #[arg(long = "backdrop.fit", value_enum)]
+    pub(crate) backdrop_fit: Option<BackdropFitArg>,
+
+    /// Backdrop pattern cell size in pixels.
+    #[arg(long = "backdrop.size")]
+    pub(crate) backdrop_size: Option<f32>,
+
+    /// Add another input as a pane next to the main one (repeatable).
+    #[arg(long = "pane", value_name = "PATH", action = clap::ArgAction::Append)]
+    pub(crate) panes: Option<Vec<String>>,
+
+    /// How panes are laid out (row, column, grid).
+    #[arg(long = "layout.direction", value_enum)]
+    pub(crate) layout_direction: Option<LayoutDirectionArg>,
+
+    /// Columns in a grid layout.
+    #[arg(long = "layout.columns")]
+    pub(crate) layout_columns: Option<usize>,
+
+    /// Space between panes.
+    #[arg(long = "layout.gap")]
+    pub(crate) layout_gap: Option<f32>,
+
+    /// Window chrome for panes (separate, shared).
+    #[arg(long = "layout.chrome", value_enum)]
+    pub(crate) layout_chrome: Option<LayoutChromeArg>,
+
+    /// Color of lines drawn between panes.
+    #[arg(long = "layout.divider")]
+    pub(crate) layout_divider: Option<String>,
+
     /// Font family.
     #[arg(long = "font.family")]
     pub(crate) font_family: Option<String>,
@@ -117,10 +224,26 @@ pub(crate) struct Args {
     #[arg(long = "font.file")]
     pub(crate) font_file: Option<String>,
 
+    /// Bold font file path.
+    #[arg(long = "font.files.bold")]
+    pub(crate) font_files_bold: Option<String>,
+
+    /// Italic font file path.
+    #[arg(long = "font.files.italic")]
+    pub(crate) font_files_italic: Option<String>,
+
+    /// Bold italic font file path.
+    #[arg(long = "font.files.bold-italic")]
+    pub(crate) font_files_bold_italic: Option<String>,
+
     /// Font fallback families (comma-separated).
     #[arg(long = "font.fallbacks", value_name = "LIST")]
     pub(crate) font_fallbacks: Option<String>,
 
+    /// OpenType features (comma-separated), e.g. ss01,-calt,cv02=2. SVG and rsvg output only.
+    #[arg(long = "font.features", value_name = "LIST")]
+    pub(crate) font_features: Option<String>,
+
     /// Font directories (comma-separated). Defaults to ~/.cryosnap/fonts.
     #[arg(long = "font.dirs", value_name = "LIST")]
     pub(crate) font_dirs: Option<String>,
@@ -163,6 +286,19 @@ pub(crate) struct Args {
     #[arg(long = "line-height")]
     pub(crate) line_height: Option<f32>,
 
+    /// Tab width in cells (defaults per language; 8 for ANSI output).
+    #[arg(long = "tab-width")]
+    pub(crate) tab_width: Option<usize>,
+
+    /// Outline text as SVG paths so output does not depend on installed fonts.
+    #[arg(
+        long = "text-to-path",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) text_to_path: Option<bool>,
+
     /// Raster scale when width/height not specified.
     #[arg(long = "raster.scale")]
     pub(crate) raster_scale: Option<f32>,
@@ -204,7 +340,7 @@ pub(crate) struct Args {
     #[arg(long = "title.size")]
     pub(crate) title_size: Option<f32>,
 
-    /// Title color.
+    /// Title color ("auto" follows the theme foreground).
     #[arg(long = "title.color")]
     pub(crate) title_color: Option<String>,
 
@@ -220,6 +356,223 @@ pub(crate) struct Args {
     #[arg(long = "title.ellipsis")]
     pub(crate) title_ellipsis: Option<String>,
 
+    /// Caption under the window.
+    #[arg(long = "caption.text")]
+    pub(crate) caption_text: Option<String>,
+
+    /// Caption alignment (left, center, right).
+    #[arg(long = "caption.align", value_enum)]
+    pub(crate) caption_align: Option<TitleAlignArg>,
+
+    /// Caption font family.
+    #[arg(long = "caption.family")]
+    pub(crate) caption_family: Option<String>,
+
+    /// Caption font size.
+    #[arg(long = "caption.size")]
+    pub(crate) caption_size: Option<f32>,
+
+    /// Caption color.
+    #[arg(long = "caption.color")]
+    pub(crate) caption_color: Option<String>,
+
+    /// Caption opacity (0-1).
+    #[arg(long = "caption.opacity")]
+    pub(crate) caption_opacity: Option<f32>,
+
+    /// Footer author.
+    #[arg(long = "footer.author")]
+    pub(crate) footer_author: Option<String>,
+
+    /// Footer URL.
+    #[arg(long = "footer.url")]
+    pub(crate) footer_url: Option<String>,
+
+    /// Footer date ("today" for the current date).
+    #[arg(long = "footer.date")]
+    pub(crate) footer_date: Option<String>,
+
+    /// Separator between footer parts.
+    #[arg(long = "footer.separator")]
+    pub(crate) footer_separator: Option<String>,
+
+    /// Footer alignment (left, center, right).
+    #[arg(long = "footer.align", value_enum)]
+    pub(crate) footer_align: Option<TitleAlignArg>,
+
+    /// Footer font family.
+    #[arg(long = "footer.family")]
+    pub(crate) footer_family: Option<String>,
+
+    /// Footer font size.
+    #[arg(long = "footer.size")]
+    pub(crate) footer_size: Option<f32>,
+
+    /// Footer color.
+    #[arg(long = "footer.color")]
+    pub(crate) footer_color: Option<String>,
+
+    /// Footer opacity (0-1).
+    #[arg(long = "footer.opacity")]
+    pub(crate) footer_opacity: Option<f32>,
+
+    /// Watermark text.
+    #[arg(long = "watermark.text")]
+    pub(crate) watermark_text: Option<String>,
+
+    /// Watermark logo (PNG or JPEG).
+    #[arg(long = "watermark.image")]
+    pub(crate) watermark_image: Option<String>,
+
+    /// Watermark corner (top-left, top-right, bottom-left, bottom-right).
+    #[arg(long = "watermark.position", value_enum)]
+    pub(crate) watermark_position: Option<WatermarkPositionArg>,
+
+    /// Watermark font family.
+    #[arg(long = "watermark.family")]
+    pub(crate) watermark_family: Option<String>,
+
+    /// Watermark font size; the logo is twice as tall.
+    #[arg(long = "watermark.size")]
+    pub(crate) watermark_size: Option<f32>,
+
+    /// Watermark text color.
+    #[arg(long = "watermark.color")]
+    pub(crate) watermark_color: Option<String>,
+
+    /// Watermark opacity (0-1).
+    #[arg(long = "watermark.opacity")]
+    pub(crate) watermark_opacity: Option<f32>,
+
+    /// Line number color ("auto" follows the theme gutter).
+    #[arg(long = "gutter.color")]
+    pub(crate) gutter_color: Option<String>,
+
+    /// Show a git blame column (commit, author, age) in the gutter.
+    #[arg(
+        long = "gutter.blame",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) gutter_blame: Option<bool>,
+
+    /// Number shown for the first rendered line.
+    #[arg(long = "gutter.start")]
+    pub(crate) gutter_start: Option<usize>,
+
+    /// Minimum number of digits in the line number column.
+    #[arg(long = "gutter.min-digits")]
+    pub(crate) gutter_min_digits: Option<usize>,
+
+    /// String between line numbers and code.
+    #[arg(long = "gutter.separator")]
+    pub(crate) gutter_separator: Option<String>,
+
+    /// Draw a vertical line between the gutter and the code.
+    #[arg(
+        long = "gutter.separator-line",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) gutter_separator_line: Option<bool>,
+
+    /// Gutter background ("none", "auto" follows the theme, or a color).
+    #[arg(long = "gutter.background")]
+    pub(crate) gutter_background: Option<String>,
+
+    /// Line number alignment (left, right).
+    #[arg(long = "gutter.align", value_enum)]
+    pub(crate) gutter_align: Option<GutterAlignArg>,
+
+    /// Number lines relative to the focus line.
+    #[arg(
+        long = "gutter.relative",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) gutter_relative: Option<bool>,
+
+    /// Source line to highlight in the gutter.
+    #[arg(long = "gutter.focus")]
+    pub(crate) gutter_focus: Option<usize>,
+
+    /// Focus line number color ("auto" follows the theme foreground).
+    #[arg(long = "gutter.focus-color")]
+    pub(crate) gutter_focus_color: Option<String>,
+
+    /// Redact text matching a regex (repeatable).
+    #[arg(long = "redact", value_name = "REGEX", action = clap::ArgAction::Append)]
+    pub(crate) redact_patterns: Option<Vec<String>>,
+
+    /// Redact common API key and token formats and email addresses. Hostnames
+    /// and other internal names need `--redact` patterns.
+    #[arg(
+        long = "redact.builtin",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) redact_builtin: Option<bool>,
+
+    /// Redact high-entropy strings.
+    #[arg(
+        long = "redact.entropy",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) redact_entropy: Option<bool>,
+
+    /// Redaction block style (solid, blur).
+    #[arg(long = "redact.style", value_enum)]
+    pub(crate) redact_style: Option<RedactStyleArg>,
+
+    /// Redaction block color ("auto" uses the text color).
+    #[arg(long = "redact.color")]
+    pub(crate) redact_color: Option<String>,
+
+    /// Draw markers for spaces, tabs and line ends.
+    #[arg(
+        long = "whitespace.visible",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) whitespace_visible: Option<bool>,
+
+    /// Draw vertical indent guides.
+    #[arg(
+        long = "whitespace.indent-guides",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) whitespace_indent_guides: Option<bool>,
+
+    /// Color brackets by nesting depth.
+    #[arg(
+        long = "brackets.rainbow",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) brackets_rainbow: Option<bool>,
+
+    /// Bracket depth colors (comma-separated).
+    #[arg(long = "brackets.palette", value_name = "LIST")]
+    pub(crate) brackets_palette: Option<String>,
+
+    /// Emphasize the bracket pair at LINE:COLUMN.
+    #[arg(long = "brackets.match", value_name = "LINE:COLUMN")]
+    pub(crate) brackets_match: Option<String>,
+
+    /// Whitespace marker and indent guide color ("auto" follows the theme).
+    #[arg(long = "whitespace.color")]
+    pub(crate) whitespace_color: Option<String>,
+
     /// Enable font ligatures.
     #[arg(
         long = "font.ligatures",
@@ -229,6 +582,42 @@ pub(crate) struct Args {
     )]
     pub(crate) font_ligatures: Option<bool>,
 
+    /// Position every character on its terminal cell.
+    #[arg(
+        long = "font.grid",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) font_grid: Option<bool>,
+
+    /// Embed only the glyphs the image uses.
+    #[arg(
+        long = "font.subset",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) font_subset: Option<bool>,
+
+    /// Embed fonts as WOFF2.
+    #[arg(
+        long = "font.woff2",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) font_woff2: Option<bool>,
+
+    /// Embed subsets of the fallback fonts used in SVG output.
+    #[arg(
+        long = "font.embed-fallbacks",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub(crate) font_embed_fallbacks: Option<bool>,
+
     /// Optimize PNG output (lossless).
     #[arg(
         long = "png-opt",
@@ -279,6 +668,14 @@ pub(crate) struct Args {
     #[arg(short = 'x', long)]
     pub(crate) execute: Option<String>,
 
+    /// Run a command and show its output below the code.
+    #[arg(long)]
+    pub(crate) run: Option<String>,
+
+    /// Color of the line between the code and the run output.
+    #[arg(long = "run.divider")]
+    pub(crate) run_divider: Option<String>,
+
     /// Capture output from tmux capture-pane.
     #[arg(long)]
     pub(crate) tmux: bool,
@@ -386,6 +783,150 @@ impl From<PngQuantPresetArg> for PngQuantPreset {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum DocumentFormatArg {
+    Auto,
+    Markdown,
+    Rst,
+    Ipynb,
+}
+
+impl From<DocumentFormatArg> for DocumentFormat {
+    fn from(value: DocumentFormatArg) -> Self {
+        match value {
+            DocumentFormatArg::Auto => DocumentFormat::Auto,
+            DocumentFormatArg::Markdown => DocumentFormat::Markdown,
+            DocumentFormatArg::Rst => DocumentFormat::Rst,
+            DocumentFormatArg::Ipynb => DocumentFormat::Ipynb,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum GutterAlignArg {
+    Left,
+    Right,
+}
+
+impl From<GutterAlignArg> for GutterAlign {
+    fn from(value: GutterAlignArg) -> Self {
+        match value {
+            GutterAlignArg::Left => GutterAlign::Left,
+            GutterAlignArg::Right => GutterAlign::Right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum ChromeStyleArg {
+    Macos,
+    Windows,
+    Gnome,
+    Minimal,
+    None,
+}
+
+impl From<ChromeStyleArg> for ChromeStyle {
+    fn from(value: ChromeStyleArg) -> Self {
+        match value {
+            ChromeStyleArg::Macos => ChromeStyle::Macos,
+            ChromeStyleArg::Windows => ChromeStyle::Windows,
+            ChromeStyleArg::Gnome => ChromeStyle::Gnome,
+            ChromeStyleArg::Minimal => ChromeStyle::Minimal,
+            ChromeStyleArg::None => ChromeStyle::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum BackdropStyleArg {
+    None,
+    Solid,
+    Linear,
+    Radial,
+    Image,
+    Dots,
+    Grid,
+    Noise,
+}
+
+impl From<BackdropStyleArg> for BackdropStyle {
+    fn from(value: BackdropStyleArg) -> Self {
+        match value {
+            BackdropStyleArg::None => BackdropStyle::None,
+            BackdropStyleArg::Solid => BackdropStyle::Solid,
+            BackdropStyleArg::Linear => BackdropStyle::Linear,
+            BackdropStyleArg::Radial => BackdropStyle::Radial,
+            BackdropStyleArg::Image => BackdropStyle::Image,
+            BackdropStyleArg::Dots => BackdropStyle::Dots,
+            BackdropStyleArg::Grid => BackdropStyle::Grid,
+            BackdropStyleArg::Noise => BackdropStyle::Noise,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum BackdropFitArg {
+    Cover,
+    Contain,
+}
+
+impl From<BackdropFitArg> for BackdropFit {
+    fn from(value: BackdropFitArg) -> Self {
+        match value {
+            BackdropFitArg::Cover => BackdropFit::Cover,
+            BackdropFitArg::Contain => BackdropFit::Contain,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum LayoutDirectionArg {
+    Row,
+    Column,
+    Grid,
+}
+
+impl From<LayoutDirectionArg> for LayoutDirection {
+    fn from(value: LayoutDirectionArg) -> Self {
+        match value {
+            LayoutDirectionArg::Row => LayoutDirection::Row,
+            LayoutDirectionArg::Column => LayoutDirection::Column,
+            LayoutDirectionArg::Grid => LayoutDirection::Grid,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum LayoutChromeArg {
+    Separate,
+    Shared,
+}
+
+impl From<LayoutChromeArg> for LayoutChrome {
+    fn from(value: LayoutChromeArg) -> Self {
+        match value {
+            LayoutChromeArg::Separate => LayoutChrome::Separate,
+            LayoutChromeArg::Shared => LayoutChrome::Shared,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum RedactStyleArg {
+    Solid,
+    Blur,
+}
+
+impl From<RedactStyleArg> for RedactStyle {
+    fn from(value: RedactStyleArg) -> Self {
+        match value {
+            RedactStyleArg::Solid => RedactStyle::Solid,
+            RedactStyleArg::Blur => RedactStyle::Blur,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub(crate) enum TitleAlignArg {
     Left,
@@ -403,6 +944,25 @@ impl From<TitleAlignArg> for TitleAlign {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum WatermarkPositionArg {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl From<WatermarkPositionArg> for WatermarkPosition {
+    fn from(value: WatermarkPositionArg) -> Self {
+        match value {
+            WatermarkPositionArg::TopLeft => WatermarkPosition::TopLeft,
+            WatermarkPositionArg::TopRight => WatermarkPosition::TopRight,
+            WatermarkPositionArg::BottomLeft => WatermarkPosition::BottomLeft,
+            WatermarkPositionArg::BottomRight => WatermarkPosition::BottomRight,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub(crate) enum TitlePathStyleArg {
     Absolute,
@@ -519,5 +1079,111 @@ mod tests {
             TitlePathStyle::from(TitlePathStyleArg::Basename),
             TitlePathStyle::Basename
         ));
+
+        assert_eq!(
+            DocumentFormat::from(DocumentFormatArg::Auto),
+            DocumentFormat::Auto
+        );
+        assert_eq!(
+            DocumentFormat::from(DocumentFormatArg::Markdown),
+            DocumentFormat::Markdown
+        );
+        assert_eq!(
+            DocumentFormat::from(DocumentFormatArg::Rst),
+            DocumentFormat::Rst
+        );
+        assert_eq!(
+            DocumentFormat::from(DocumentFormatArg::Ipynb),
+            DocumentFormat::Ipynb
+        );
+
+        assert_eq!(ChromeStyle::from(ChromeStyleArg::Macos), ChromeStyle::Macos);
+        assert_eq!(
+            ChromeStyle::from(ChromeStyleArg::Windows),
+            ChromeStyle::Windows
+        );
+        assert_eq!(ChromeStyle::from(ChromeStyleArg::Gnome), ChromeStyle::Gnome);
+        assert_eq!(
+            ChromeStyle::from(ChromeStyleArg::Minimal),
+            ChromeStyle::Minimal
+        );
+        assert_eq!(ChromeStyle::from(ChromeStyleArg::None), ChromeStyle::None);
+        assert_eq!(
+            BackdropStyle::from(BackdropStyleArg::None),
+            BackdropStyle::None
+        );
+        assert_eq!(
+            BackdropStyle::from(BackdropStyleArg::Solid),
+            BackdropStyle::Solid
+        );
+        assert_eq!(
+            BackdropStyle::from(BackdropStyleArg::Linear),
+            BackdropStyle::Linear
+        );
+        assert_eq!(
+            BackdropStyle::from(BackdropStyleArg::Radial),
+            BackdropStyle::Radial
+        );
+        assert_eq!(
+            BackdropStyle::from(BackdropStyleArg::Image),
+            BackdropStyle::Image
+        );
+        assert_eq!(
+            BackdropStyle::from(BackdropStyleArg::Dots),
+            BackdropStyle::Dots
+        );
+        assert_eq!(
+            BackdropStyle::from(BackdropStyleArg::Grid),
+            BackdropStyle::Grid
+        );
+        assert_eq!(
+            BackdropStyle::from(BackdropStyleArg::Noise),
+            BackdropStyle::Noise
+        );
+        assert_eq!(BackdropFit::from(BackdropFitArg::Cover), BackdropFit::Cover);
+        assert_eq!(
+            BackdropFit::from(BackdropFitArg::Contain),
+            BackdropFit::Contain
+        );
+        assert_eq!(
+            LayoutDirection::from(LayoutDirectionArg::Row),
+            LayoutDirection::Row
+        );
+        assert_eq!(
+            LayoutDirection::from(LayoutDirectionArg::Column),
+            LayoutDirection::Column
+        );
+        assert_eq!(
+            LayoutDirection::from(LayoutDirectionArg::Grid),
+            LayoutDirection::Grid
+        );
+        assert_eq!(
+            LayoutChrome::from(LayoutChromeArg::Separate),
+            LayoutChrome::Separate
+        );
+        assert_eq!(
+            LayoutChrome::from(LayoutChromeArg::Shared),
+            LayoutChrome::Shared
+        );
+        assert_eq!(
+            WatermarkPosition::from(WatermarkPositionArg::TopLeft),
+            WatermarkPosition::TopLeft
+        );
+        assert_eq!(
+            WatermarkPosition::from(WatermarkPositionArg::TopRight),
+            WatermarkPosition::TopRight
+        );
+        assert_eq!(
+            WatermarkPosition::from(WatermarkPositionArg::BottomLeft),
+            WatermarkPosition::BottomLeft
+        );
+        assert_eq!(
+            WatermarkPosition::from(WatermarkPositionArg::BottomRight),
+            WatermarkPosition::BottomRight
+        );
+        assert_eq!(RedactStyle::from(RedactStyleArg::Solid), RedactStyle::Solid);
+        assert_eq!(RedactStyle::from(RedactStyleArg::Blur), RedactStyle::Blur);
+        assert_eq!(GutterAlign::from(GutterAlignArg::Left), GutterAlign::Left);
+        assert_eq!(GutterAlign::from(GutterAlignArg::Right), GutterAlign::Right);
     }
 }
@@ -1,3 +1,4 @@
+use cryosnap_core::LineSelection;
 use std::error::Error;
 
 pub(crate) fn parse_box(input: &str) -> Result<Vec<f32>, Box<dyn Error>> {
@@ -14,18 +15,8 @@ pub(crate) fn parse_box(input: &str) -> Result<Vec<f32>, Box<dyn Error>> {
         .collect::<Result<Vec<f32>, _>>()?)
 }
 
-pub(crate) fn parse_lines(input: &str) -> Result<Vec<i32>, Box<dyn Error>> {
-    let parts: Vec<&str> = input.split([',', ' ']).filter(|s| !s.is_empty()).collect();
-    if parts.is_empty() {
-        return Ok(vec![]);
-    }
-    if !matches!(parts.len(), 1 | 2) {
-        return Err("invalid lines length".into());
-    }
-    Ok(parts
-        .iter()
-        .map(|part| part.parse::<i32>())
-        .collect::<Result<Vec<i32>, _>>()?)
+pub(crate) fn parse_lines(input: &str) -> Result<LineSelection, Box<dyn Error>> {
+    Ok(input.parse::<LineSelection>()?)
 }
 
 pub(crate) fn parse_font_fallbacks(input: &str) -> Result<Vec<String>, Box<dyn Error>> {
@@ -41,6 +32,28 @@ pub(crate) fn parse_font_fallbacks(input: &str) -> Result<Vec<String>, Box<dyn E
         .collect())
 }
 
+pub(crate) fn parse_font_features(input: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(input
+        .split(',')
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_string())
+        .collect())
+}
+
+pub(crate) fn parse_palette(input: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let colors = input
+        .split(',')
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>();
+    if colors.is_empty() {
+        return Err("palette must contain at least one color".into());
+    }
+    Ok(colors)
+}
+
 pub(crate) fn parse_font_dirs(input: &str) -> Result<Vec<String>, Box<dyn Error>> {
     let trimmed = input.trim();
     if trimmed.is_empty() {
@@ -91,7 +104,16 @@ mod tests {
     #[test]
     fn parse_lines_accepts_values() {
         let out = parse_lines("2,5").expect("parse");
-        assert_eq!(out, vec![2, 5]);
+        assert_eq!(out, LineSelection::Window(vec![2, 5]));
+    }
+
+    #[test]
+    fn parse_lines_accepts_ranges_and_patterns() {
+        let out = parse_lines("1-5,40-60").expect("parse");
+        assert_eq!(out.to_string(), "1..5,40..60");
+        let out = parse_lines("/fn main/../^}/").expect("parse");
+        assert_eq!(out.to_string(), "/fn main/../^}/");
+        assert!(parse_lines("/unterminated").is_err());
     }
 
     #[test]
@@ -102,7 +124,7 @@ mod tests {
     #[test]
     fn parse_lines_empty_defaults() {
         let out = parse_lines("").expect("parse");
-        assert!(out.is_empty());
+        assert_eq!(out, LineSelection::Window(vec![]));
     }
 
     #[test]
@@ -129,6 +151,20 @@ mod tests {
         assert!(out.is_empty());
     }
 
+    #[test]
+    fn parse_font_features_splits_and_trims() {
+        let out = parse_font_features("ss01, -calt,,cv02=2").expect("parse");
+        assert_eq!(out, vec!["ss01", "-calt", "cv02=2"]);
+        assert!(parse_font_features(" ").expect("parse").is_empty());
+    }
+
+    #[test]
+    fn parse_palette_requires_colors() {
+        let out = parse_palette("#fff, red").expect("parse");
+        assert_eq!(out, vec!["#fff", "red"]);
+        assert!(parse_palette(" , ").is_err());
+    }
+
     #[test]
     fn parse_font_dirs_splits_and_trims() {
         let out = parse_font_dirs(" /a, , /b ").expect("parse");
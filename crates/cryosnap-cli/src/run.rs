@@ -3,11 +3,12 @@ use crate::config::{load_config, save_user_config};
 use crate::interactive::run_interactive;
 use crate::io::{print_wrote, read_stdin_with, write_output_with_tty};
 use crate::parse::{
-    parse_box, parse_font_dirs, parse_font_fallbacks, parse_lines, parse_timeout_ms,
+    parse_box, parse_font_dirs, parse_font_fallbacks, parse_font_features, parse_lines,
+    parse_palette, parse_timeout_ms,
 };
 use crate::tmux::{capture_tmux_output, tmux_title};
 use clap::{CommandFactory, Parser};
-use cryosnap_core::{InputSource, OutputFormat, RenderRequest};
+use cryosnap_core::{InputSource, OutputFormat, Pane, PaneSpec, RenderRequest};
 use std::error::Error;
 use std::path::{Path, PathBuf};
 
@@ -56,9 +57,46 @@ pub(crate) fn run_with(
     if let Some(lines) = args.lines {
         config.lines = parse_lines(&lines)?;
     }
+    if let Some(elision) = args.elision {
+        config.elision = elision;
+    }
+    if let Some(dedent) = args.dedent {
+        config.dedent.enabled = dedent;
+    }
+    if let Some(indent) = args.dedent_indent {
+        config.dedent.enabled = true;
+        config.dedent.indent = indent;
+    }
+    if let Some(symbol) = args.symbol {
+        config.symbol = Some(symbol);
+    }
+    if let Some(index) = args.block_index {
+        config.block.index = Some(index);
+    }
+    if let Some(label) = args.block_label {
+        config.block.label = Some(label);
+    }
+    if let Some(heading) = args.block_heading {
+        config.block.heading = Some(heading);
+    }
+    if let Some(format) = args.block_format {
+        config.block.format = format.into();
+    }
     if let Some(window) = args.window {
         config.window_controls = window;
     }
+    if let Some(style) = args.chrome_style {
+        config.chrome.style = style.into();
+    }
+    if let Some(tabs) = args.chrome_tabs {
+        config.chrome.tabs = tabs;
+    }
+    if let Some(tabs) = args.chrome_extra_tabs {
+        config.chrome.extra_tabs = tabs;
+    }
+    if let Some(active) = args.chrome_active_tab {
+        config.chrome.active_tab = active;
+    }
     if let Some(show) = args.show_line_numbers {
         config.show_line_numbers = show;
     }
@@ -80,15 +118,69 @@ pub(crate) fn run_with(
     if let Some(y) = args.shadow_y {
         config.shadow.y = y;
     }
+    if let Some(style) = args.backdrop_style {
+        config.backdrop.style = style.into();
+    }
+    if let Some(colors) = args.backdrop_colors {
+        config.backdrop.colors = parse_palette(&colors)?;
+    }
+    if let Some(angle) = args.backdrop_angle {
+        config.backdrop.angle = angle;
+    }
+    if let Some(image) = args.backdrop_image {
+        config.backdrop.image = Some(image);
+    }
+    if let Some(fit) = args.backdrop_fit {
+        config.backdrop.fit = fit.into();
+    }
+    if let Some(size) = args.backdrop_size {
+        config.backdrop.size = size;
+    }
+    if let Some(panes) = args.panes {
+        config.layout.panes = panes
+            .into_iter()
+            .map(|input| PaneSpec {
+                input: Some(input),
+                ..PaneSpec::default()
+            })
+            .collect();
+    }
+    if let Some(direction) = args.layout_direction {
+        config.layout.direction = direction.into();
+    }
+    if let Some(columns) = args.layout_columns {
+        config.layout.columns = columns;
+    }
+    if let Some(gap) = args.layout_gap {
+        config.layout.gap = gap;
+    }
+    if let Some(chrome) = args.layout_chrome {
+        config.layout.chrome = chrome.into();
+    }
+    if let Some(divider) = args.layout_divider {
+        config.layout.divider = Some(divider);
+    }
     if let Some(family) = args.font_family {
         config.font.family = family;
     }
     if let Some(file) = args.font_file {
         config.font.file = Some(file);
     }
+    if let Some(file) = args.font_files_bold {
+        config.font.files.bold = Some(file);
+    }
+    if let Some(file) = args.font_files_italic {
+        config.font.files.italic = Some(file);
+    }
+    if let Some(file) = args.font_files_bold_italic {
+        config.font.files.bold_italic = Some(file);
+    }
     if let Some(fallbacks) = args.font_fallbacks {
         config.font.fallbacks = parse_font_fallbacks(&fallbacks)?;
     }
+    if let Some(features) = args.font_features {
+        config.font.features = parse_font_features(&features)?;
+    }
     if let Some(dirs) = args.font_dirs {
         config.font.dirs = parse_font_dirs(&dirs)?;
     }
@@ -107,6 +199,12 @@ pub(crate) fn run_with(
     if let Some(line_height) = args.line_height {
         config.line_height = line_height;
     }
+    if let Some(text_to_path) = args.text_to_path {
+        config.text_to_path = text_to_path;
+    }
+    if let Some(tab_width) = args.tab_width {
+        config.tab_width = Some(tab_width);
+    }
     if let Some(scale) = args.raster_scale {
         config.raster.scale = scale;
     }
@@ -119,9 +217,27 @@ pub(crate) fn run_with(
     if let Some(ligatures) = args.font_ligatures {
         config.font.ligatures = ligatures;
     }
+    if let Some(grid) = args.font_grid {
+        config.font.grid = grid;
+    }
+    if let Some(subset) = args.font_subset {
+        config.font.subset = subset;
+    }
+    if let Some(woff2) = args.font_woff2 {
+        config.font.woff2 = woff2;
+    }
+    if let Some(embed) = args.font_embed_fallbacks {
+        config.font.embed_fallbacks = embed;
+    }
     if let Some(mode) = args.font_system_fallback {
         config.font.system_fallback = mode.into();
     }
+    if let Some(command) = args.run {
+        config.run.command = Some(command);
+    }
+    if let Some(divider) = args.run_divider {
+        config.run.divider = divider;
+    }
     if let Some(timeout) = args.execute_timeout {
         config.execute_timeout_ms = parse_timeout_ms(&timeout)?;
     }
@@ -183,6 +299,138 @@ pub(crate) fn run_with(
     if let Some(ellipsis) = args.title_ellipsis {
         config.title.ellipsis = ellipsis;
     }
+    if let Some(text) = args.caption_text {
+        config.caption.text = Some(text);
+    }
+    if let Some(align) = args.caption_align {
+        config.caption.align = align.into();
+    }
+    if let Some(family) = args.caption_family {
+        config.caption.family = Some(family);
+    }
+    if let Some(size) = args.caption_size {
+        config.caption.size = size;
+    }
+    if let Some(color) = args.caption_color {
+        config.caption.color = color;
+    }
+    if let Some(opacity) = args.caption_opacity {
+        config.caption.opacity = opacity;
+    }
+    if let Some(author) = args.footer_author {
+        config.footer.author = Some(author);
+    }
+    if let Some(url) = args.footer_url {
+        config.footer.url = Some(url);
+    }
+    if let Some(date) = args.footer_date {
+        config.footer.date = Some(date);
+    }
+    if let Some(separator) = args.footer_separator {
+        config.footer.separator = separator;
+    }
+    if let Some(align) = args.footer_align {
+        config.footer.align = align.into();
+    }
+    if let Some(family) = args.footer_family {
+        config.footer.family = Some(family);
+    }
+    if let Some(size) = args.footer_size {
+        config.footer.size = size;
+    }
+    if let Some(color) = args.footer_color {
+        config.footer.color = color;
+    }
+    if let Some(opacity) = args.footer_opacity {
+        config.footer.opacity = opacity;
+    }
+    if let Some(text) = args.watermark_text {
+        config.watermark.text = Some(text);
+    }
+    if let Some(image) = args.watermark_image {
+        config.watermark.image = Some(image);
+    }
+    if let Some(position) = args.watermark_position {
+        config.watermark.position = position.into();
+    }
+    if let Some(family) = args.watermark_family {
+        config.watermark.family = Some(family);
+    }
+    if let Some(size) = args.watermark_size {
+        config.watermark.size = size;
+    }
+    if let Some(color) = args.watermark_color {
+        config.watermark.color = color;
+    }
+    if let Some(opacity) = args.watermark_opacity {
+        config.watermark.opacity = opacity;
+    }
+    if let Some(color) = args.gutter_color {
+        config.gutter.color = color;
+    }
+    if let Some(blame) = args.gutter_blame {
+        config.gutter.blame = blame;
+    }
+    if let Some(start) = args.gutter_start {
+        config.gutter.start = Some(start);
+    }
+    if let Some(min_digits) = args.gutter_min_digits {
+        config.gutter.min_digits = min_digits;
+    }
+    if let Some(separator) = args.gutter_separator {
+        config.gutter.separator = separator;
+    }
+    if let Some(separator_line) = args.gutter_separator_line {
+        config.gutter.separator_line = separator_line;
+    }
+    if let Some(background) = args.gutter_background {
+        config.gutter.background = background;
+    }
+    if let Some(align) = args.gutter_align {
+        config.gutter.align = align.into();
+    }
+    if let Some(relative) = args.gutter_relative {
+        config.gutter.relative = relative;
+    }
+    if let Some(focus) = args.gutter_focus {
+        config.gutter.focus = Some(focus);
+    }
+    if let Some(color) = args.gutter_focus_color {
+        config.gutter.focus_color = color;
+    }
+    if let Some(patterns) = args.redact_patterns {
+        config.redact.patterns = patterns;
+    }
+    if let Some(builtin) = args.redact_builtin {
+        config.redact.builtin = builtin;
+    }
+    if let Some(entropy) = args.redact_entropy {
+        config.redact.entropy = entropy;
+    }
+    if let Some(style) = args.redact_style {
+        config.redact.style = style.into();
+    }
+    if let Some(color) = args.redact_color {
+        config.redact.color = color;
+    }
+    if let Some(visible) = args.whitespace_visible {
+        config.whitespace.visible = visible;
+    }
+    if let Some(indent_guides) = args.whitespace_indent_guides {
+        config.whitespace.indent_guides = indent_guides;
+    }
+    if let Some(color) = args.whitespace_color {
+        config.whitespace.color = color;
+    }
+    if let Some(rainbow) = args.brackets_rainbow {
+        config.brackets.rainbow = rainbow;
+    }
+    if let Some(palette) = args.brackets_palette {
+        config.brackets.palette = parse_palette(&palette)?;
+    }
+    if let Some(position) = args.brackets_match {
+        config.brackets.match_at = Some(position);
+    }
 
     if args.tmux {
         if args.execute.is_some() || args.input.is_some() {
@@ -232,8 +480,10 @@ pub(crate) fn run_with(
     } else if let Some(input) = input_arg {
         if input == "-" {
             InputSource::Text(read_stdin_with(stdin_override)?)
-        } else {
+        } else if Path::new(&input).exists() {
             InputSource::File(PathBuf::from(input))
+        } else {
+            InputSource::git(&input).unwrap_or_else(|| InputSource::File(PathBuf::from(input)))
         }
     } else if !stdin_is_tty {
         InputSource::Text(read_stdin_with(stdin_override)?)
@@ -243,6 +493,15 @@ pub(crate) fn run_with(
         println!();
         return Ok(());
     };
+    let panes = if config.layout.panes.is_empty() {
+        None
+    } else {
+        let mut panes = vec![Pane::new(input.clone())];
+        for spec in &config.layout.panes {
+            panes.push(spec.to_pane()?);
+        }
+        Some(panes)
+    };
 
     if let Some(output) = args.output.as_ref() {
         if let Some(expanded) = expand_output_pattern(output)? {
@@ -263,16 +522,21 @@ pub(crate) fn run_with(
                 .iter()
                 .any(|(_, format)| matches!(format, OutputFormat::Webp));
 
-            let planned = cryosnap_core::render_svg_planned(&input, &config)?;
-            let svg = planned.bytes;
-            let png_webp = if wants_png && wants_webp {
-                Some(cryosnap_core::render_png_webp_from_svg_once(
-                    &svg,
-                    &config,
-                    planned.needs_system_fonts,
-                )?)
+            let (svg, png_webp) = if let Some(panes) = panes.as_deref() {
+                let svg = cryosnap_core::render_panes(panes, &config, OutputFormat::Svg)?;
+                (svg.bytes, None)
             } else {
-                None
+                let planned = cryosnap_core::render_svg_planned(&input, &config)?;
+                let png_webp = if wants_png && wants_webp {
+                    Some(cryosnap_core::render_png_webp_from_svg_once(
+                        &planned.bytes,
+                        &config,
+                        planned.needs_system_fonts,
+                    )?)
+                } else {
+                    None
+                };
+                (planned.bytes, png_webp)
             };
 
             for (path, format) in outputs {
@@ -302,14 +566,15 @@ pub(crate) fn run_with(
         format = OutputFormat::Png;
         format_arg = Some(FormatArg::Png);
     }
-    let request = RenderRequest {
-        input,
-        config,
-        format,
+    let result = match panes {
+        Some(panes) => cryosnap_core::render_panes(&panes, &config, format)?,
+        None => cryosnap_core::render(&RenderRequest {
+            input,
+            config,
+            format,
+        })?,
     };
 
-    let result = cryosnap_core::render(&request)?;
-
     write_output_with_tty(
         result,
         args.output.as_ref(),
@@ -386,8 +651,9 @@ fn expand_output_pattern(output: &Path) -> Result<Option<Vec<PathBuf>>, Box<dyn
 mod tests {
     use super::*;
     use crate::args::{
-        FontCjkRegionArg, FontSystemFallbackArg, PngQuantPresetArg, PngStripArg, RasterBackendArg,
-        TitleAlignArg, TitlePathStyleArg,
+        BackdropFitArg, BackdropStyleArg, ChromeStyleArg, DocumentFormatArg, FontCjkRegionArg,
+        FontSystemFallbackArg, LayoutChromeArg, LayoutDirectionArg, PngQuantPresetArg, PngStripArg,
+        RasterBackendArg, TitleAlignArg, TitlePathStyleArg, WatermarkPositionArg,
     };
     use crate::test_utils::{cwd_lock, env_lock};
     use tempfile::tempdir;
@@ -497,7 +763,12 @@ mod tests {
         args.language = Some("rust".to_string());
         args.wrap = Some(80);
         args.lines = Some("1,2".to_string());
+        args.elision = Some("// ...".to_string());
         args.window = Some(true);
+        args.chrome_style = Some(ChromeStyleArg::Gnome);
+        args.chrome_tabs = Some(true);
+        args.chrome_extra_tabs = Some(vec!["src/lib.rs".to_string()]);
+        args.chrome_active_tab = Some(1);
         args.show_line_numbers = Some(true);
         args.border_radius = Some(4.0);
         args.border_width = Some(1.0);
@@ -512,6 +783,7 @@ mod tests {
         args.raster_max_pixels = Some(1_000_000);
         args.raster_backend = Some(RasterBackendArg::Resvg);
         args.font_ligatures = Some(false);
+        args.font_grid = Some(true);
         args.execute_timeout = Some("500ms".to_string());
         args.png_opt = Some(false);
         args.png_opt_level = Some(3);
@@ -530,12 +802,122 @@ mod tests {
         args.title_opacity = Some(0.7);
         args.title_max_width = Some(30);
         args.title_ellipsis = Some("..".to_string());
+        args.gutter_color = Some("#555555".to_string());
 
         let result = run_with(args, false, false, Some("hello"));
         assert!(result.is_ok());
         assert!(out_path.exists());
     }
 
+    #[test]
+    fn run_with_applies_backdrop() {
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some("-".to_string());
+        args.output = Some(out_path.clone());
+        args.margin = Some("32".to_string());
+        args.backdrop_style = Some(BackdropStyleArg::Dots);
+        args.backdrop_colors = Some("#0f172a, #334155".to_string());
+        args.backdrop_angle = Some(45.0);
+        args.backdrop_fit = Some(BackdropFitArg::Contain);
+        args.backdrop_size = Some(20.0);
+
+        let result = run_with(args, false, false, Some("hello"));
+        assert!(result.is_ok());
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content
+            .contains(r#"<pattern id="backdrop" patternUnits="userSpaceOnUse" width="20.00""#));
+        assert!(content.contains(r##"fill="#334155""##));
+
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some("-".to_string());
+        args.output = Some(dir.path().join("image.svg"));
+        args.backdrop_style = Some(BackdropStyleArg::Image);
+        args.backdrop_image = Some(dir.path().join("missing.png").to_string_lossy().to_string());
+        assert!(run_with(args, false, false, Some("hello")).is_err());
+    }
+
+    #[test]
+    fn run_with_draws_caption_footer_and_watermark() {
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some("-".to_string());
+        args.output = Some(out_path.clone());
+        args.caption_text = Some("Caption".to_string());
+        args.caption_align = Some(TitleAlignArg::Left);
+        args.caption_family = Some("serif".to_string());
+        args.caption_size = Some(16.0);
+        args.caption_color = Some("#111111".to_string());
+        args.caption_opacity = Some(0.9);
+        args.footer_author = Some("Author".to_string());
+        args.footer_url = Some("example.com".to_string());
+        args.footer_date = Some("2026-01-01".to_string());
+        args.footer_separator = Some(" | ".to_string());
+        args.footer_align = Some(TitleAlignArg::Right);
+        args.footer_family = Some("sans-serif".to_string());
+        args.footer_size = Some(10.0);
+        args.footer_color = Some("#222222".to_string());
+        args.footer_opacity = Some(0.8);
+        args.watermark_text = Some("brand".to_string());
+        args.watermark_position = Some(WatermarkPositionArg::TopRight);
+        args.watermark_family = Some("monospace".to_string());
+        args.watermark_size = Some(9.0);
+        args.watermark_color = Some("#333333".to_string());
+        args.watermark_opacity = Some(0.4);
+
+        let result = run_with(args, false, false, Some("hello"));
+        assert!(result.is_ok());
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains(r#"font-family="serif" font-size="16.00px" text-anchor="start""#));
+        assert!(content.contains(">Author | example.com | 2026-01-01</text>"));
+        assert!(content.contains(r#"opacity="0.40"><text"#));
+        assert!(content.contains(">brand</text>"));
+
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some("-".to_string());
+        args.output = Some(dir.path().join("logo.svg"));
+        args.watermark_image = Some(dir.path().join("missing.png").to_string_lossy().to_string());
+        assert!(run_with(args, false, false, Some("hello")).is_err());
+    }
+
+    #[test]
+    fn run_with_composes_panes() {
+        let dir = tempdir().expect("temp dir");
+        let pane_path = dir.path().join("pane.rs");
+        std::fs::write(&pane_path, "fn main() {}\n").expect("write");
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some("-".to_string());
+        args.output = Some(dir.path().join("out.{svg,png}"));
+        args.window = Some(true);
+        args.panes = Some(vec![pane_path.to_string_lossy().to_string()]);
+        args.layout_direction = Some(LayoutDirectionArg::Grid);
+        args.layout_columns = Some(1);
+        args.layout_gap = Some(8.0);
+        args.layout_chrome = Some(LayoutChromeArg::Shared);
+        args.layout_divider = Some("#222222".to_string());
+
+        let result = run_with(args, false, false, Some("hello"));
+        assert!(result.is_ok());
+        let content = std::fs::read_to_string(dir.path().join("out.svg")).expect("read");
+        assert!(content.contains("pane0-contentClip"));
+        assert!(content.contains("pane1-contentClip"));
+        assert_eq!(content.matches(r##"fill="#FF5A54""##).count(), 1);
+        assert!(content.contains(r##"stroke="#222222""##));
+        assert!(dir.path().join("out.png").exists());
+
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some("-".to_string());
+        args.output = Some(dir.path().join("missing.svg"));
+        args.panes = Some(vec![dir
+            .path()
+            .join("missing.rs")
+            .to_string_lossy()
+            .to_string()]);
+        assert!(run_with(args, false, false, Some("hello")).is_err());
+    }
+
     fn asset_path(name: &str) -> PathBuf {
         let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
         manifest.join("..").join("..").join("assets").join(name)
@@ -557,6 +939,14 @@ mod tests {
         args.output = Some(out_path.clone());
         args.font_file = Some(font_file.to_string_lossy().to_string());
         args.font_fallbacks = Some("Noto Sans CJK SC".to_string());
+        args.font_features = Some("ss01, -calt".to_string());
+        args.font_files_bold = Some(font_file.to_string_lossy().to_string());
+        args.font_files_italic = Some(font_file.to_string_lossy().to_string());
+        args.font_files_bold_italic = Some(font_file.to_string_lossy().to_string());
+        args.font_subset = Some(true);
+        args.font_woff2 = Some(true);
+        args.font_embed_fallbacks = Some(true);
+        args.text_to_path = Some(false);
         args.font_dirs = Some(fonts_dir.to_string_lossy().to_string());
         args.font_cjk_region = Some(FontCjkRegionArg::Jp);
         args.font_auto_download = Some(false);
@@ -565,7 +955,259 @@ mod tests {
 
         let result = run_with(args, true, false, None);
         assert!(result.is_ok());
-        assert!(out_path.exists());
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains(r#"font-feature-settings: "ss01" 1, "calt" 0;"#));
+        assert!(content.contains("font-weight: 700; font-style: italic;"));
+        assert!(content.contains("url(data:font/woff2;base64,"));
+    }
+
+    #[test]
+    fn run_with_extracts_markdown_block() {
+        let dir = tempdir().expect("temp dir");
+        let input_path = dir.path().join("README.md");
+        let out_path = dir.path().join("out.svg");
+        std::fs::write(
+            &input_path,
+            "# Intro\n\n```sh\necho hi\n```\n\n## Usage\n\n```rust\nfn main() {}\n```\n",
+        )
+        .expect("write");
+
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some(input_path.to_string_lossy().to_string());
+        args.output = Some(out_path.clone());
+        args.block_heading = Some("usage".to_string());
+        args.block_label = Some("rust".to_string());
+        args.block_index = Some(1);
+        args.block_format = Some(DocumentFormatArg::Markdown);
+
+        run_with(args, true, false, None).expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains("main"));
+        assert!(!content.contains("echo"));
+    }
+
+    #[test]
+    fn run_with_selects_symbol() {
+        let dir = tempdir().expect("temp dir");
+        let input_path = dir.path().join("lib.rs");
+        let out_path = dir.path().join("out.svg");
+        std::fs::write(
+            &input_path,
+            "fn helper() {}\n\npub fn render() {\n    helper();\n}\n",
+        )
+        .expect("write");
+
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some(input_path.to_string_lossy().to_string());
+        args.output = Some(out_path.clone());
+        args.symbol = Some("fn render".to_string());
+
+        run_with(args, true, false, None).expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains("render"));
+        assert!(!content.contains("helper() {}"));
+    }
+
+    #[test]
+    fn run_with_git_revision_spec_reads_repository() {
+        let dir = tempdir().expect("temp dir");
+        let repo = git2::Repository::init(dir.path()).expect("init");
+        let signature = git2::Signature::now("Carol", "carol@example.com").expect("signature");
+        let mut parent = None;
+        for content in ["fn old_version() {}\n", "fn new_version() {}\n"] {
+            std::fs::write(dir.path().join("main.rs"), content).expect("write");
+            let mut index = repo.index().expect("index");
+            index
+                .add_path(std::path::Path::new("main.rs"))
+                .expect("add");
+            index.write().expect("write index");
+            let tree = repo
+                .find_tree(index.write_tree().expect("tree"))
+                .expect("find tree");
+            let parents = parent
+                .iter()
+                .map(|oid| repo.find_commit(*oid).expect("parent"))
+                .collect::<Vec<_>>();
+            let parents = parents.iter().collect::<Vec<_>>();
+            let oid = repo
+                .commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    "update",
+                    &tree,
+                    &parents,
+                )
+                .expect("commit");
+            parent = Some(oid);
+        }
+
+        let out_path = dir.path().join("out.svg");
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some(format!("{}@HEAD~1", dir.path().join("main.rs").display()));
+        args.output = Some(out_path.clone());
+        args.gutter_blame = Some(true);
+        run_with(args, true, false, None).expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains("old_version"));
+        assert!(!content.contains("new_version"));
+        assert!(content.contains("Carol"));
+    }
+
+    #[test]
+    fn run_with_redacts_secrets() {
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let args = Args::parse_from([
+            "cryosnap",
+            "-",
+            "--output",
+            out_path.to_str().expect("path"),
+            "--redact",
+            "hunter[0-9]",
+            "--redact",
+            "a,b",
+            "--redact.builtin",
+            "--redact.style",
+            "blur",
+        ]);
+        run_with(args, false, false, Some("password = hunter2")).expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains("password"));
+        assert!(!content.contains("hunter2"));
+        assert!(content.contains("redactBlur"));
+    }
+
+    #[test]
+    fn run_with_shows_whitespace() {
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let args = Args::parse_from([
+            "cryosnap",
+            "-",
+            "--output",
+            out_path.to_str().expect("path"),
+            "--language",
+            "python",
+            "--whitespace.visible",
+            "--whitespace.indent-guides",
+            "--whitespace.color",
+            "#123456",
+        ]);
+        run_with(args, false, false, Some("if x:\n\tpass  ")).expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains('\u{2192}'));
+        assert!(content.contains('\u{00B7}'));
+        assert!(content.contains('\u{00AC}'));
+        assert!(content.contains(r#"width="1""#));
+        assert!(content.contains("#123456"));
+    }
+
+    #[test]
+    fn run_with_tab_width_controls_expansion() {
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let args = Args::parse_from([
+            "cryosnap",
+            "-",
+            "--output",
+            out_path.to_str().expect("path"),
+            "--tab-width",
+            "2",
+        ]);
+        run_with(args, false, false, Some("\tx")).expect("render");
+        let content = std::fs::read_to_string(&out_path).expect("read");
+        assert!(content.contains(">  x<"));
+
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some("-".to_string());
+        args.output = Some(out_path);
+        args.tab_width = Some(0);
+        let err = run_with(args, false, false, Some("\tx")).unwrap_err();
+        assert!(err.to_string().contains("tab_width"));
+    }
+
+    #[test]
+    fn run_with_customizes_gutter() {
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let args = Args::parse_from([
+            "cryosnap",
+            "-",
+            "--output",
+            out_path.to_str().expect("path"),
+            "--show-line-numbers",
+            "--gutter.start",
+            "40",
+            "--gutter.separator",
+            " | ",
+            "--gutter.separator-line",
+            "--gutter.background",
+            "#202020",
+            "--gutter.align",
+            "left",
+            "--gutter.min-digits",
+            "2",
+        ]);
+        run_with(args, false, false, Some("a\nb")).expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains(">40 | <"));
+        assert!(content.contains(">41 | <"));
+        assert!(content.contains("terminalClip"));
+        assert!(content.contains("<line "));
+    }
+
+    #[test]
+    fn run_with_dedents_selection() {
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let args = Args::parse_from([
+            "cryosnap",
+            "-",
+            "--output",
+            out_path.to_str().expect("path"),
+            "--lines",
+            "2-3",
+            "--dedent.indent",
+            "1",
+        ]);
+        run_with(
+            args,
+            false,
+            false,
+            Some("fn a() {\n        x\n            y\n}"),
+        )
+        .expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        let text = content
+            .split('<')
+            .filter_map(|tag| tag.split_once('>').map(|(_, text)| text))
+            .collect::<String>();
+        assert!(text.contains(" x     y"));
+    }
+
+    #[test]
+    fn run_with_colors_brackets() {
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let args = Args::parse_from([
+            "cryosnap",
+            "-",
+            "--output",
+            out_path.to_str().expect("path"),
+            "--language",
+            "rust",
+            "--brackets.rainbow",
+            "--brackets.palette",
+            "#111111,#222222",
+            "--brackets.match",
+            "1:4",
+        ]);
+        run_with(args, false, false, Some("f(g(x));")).expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains("#111111"));
+        assert!(content.contains("#222222"));
+        assert!(content.contains("underline"));
     }
 
     #[test]
@@ -629,6 +1271,50 @@ mod tests {
         assert!(content.contains("<svg"));
     }
 
+    #[test]
+    fn run_with_run_command_appends_output() {
+        let _lock = env_lock().lock().expect("lock");
+        let dir = tempdir().expect("temp dir");
+        let out_path = dir.path().join("out.svg");
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some("-".to_string());
+        args.output = Some(out_path.clone());
+        args.run = Some("printf 'world'".to_string());
+        args.run_divider = Some("#654321".to_string());
+        let result = run_with(args, false, false, Some("print('world')"));
+        assert!(result.is_ok());
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains(r##"stroke="#654321""##));
+        assert!(content.contains("pane1-contentClip"));
+    }
+
+    #[test]
+    fn run_with_run_command_keeps_symbol_and_lines() {
+        let _lock = env_lock().lock().expect("lock");
+        let dir = tempdir().expect("temp dir");
+        let input_path = dir.path().join("lib.rs");
+        let out_path = dir.path().join("out.svg");
+        std::fs::write(
+            &input_path,
+            "fn helper() {}\n\npub fn render() {\n    helper();\n    done();\n}\n",
+        )
+        .expect("write");
+
+        let mut args = Args::parse_from(["cryosnap"]);
+        args.input = Some(input_path.to_string_lossy().to_string());
+        args.output = Some(out_path.clone());
+        args.symbol = Some("fn render".to_string());
+        args.lines = Some("1-2".to_string());
+        args.run = Some("printf 'world'".to_string());
+        run_with(args, true, false, None).expect("render");
+        let content = std::fs::read_to_string(out_path).expect("read");
+        assert!(content.contains("render"));
+        assert!(!content.contains("helper() {}"));
+        assert!(!content.contains("done"));
+        let output = &content[content.find("pane1-contentClip").expect("output pane")..];
+        assert!(output.contains("world"));
+    }
+
     #[cfg(unix)]
     #[test]
     fn run_with_tmux_capture_uses_fake_tmux() {
@@ -1,8 +1,10 @@
-use crate::{Line, Span, TextStyle, ANSI_TAB_WIDTH};
+use crate::layout::{grapheme_width, graphemes, line_width_cells, tab_stop_cells};
+use crate::text::{wrap_line, WrapRow};
+use crate::{Line, Span, TabCell, TextStyle};
 
-pub(crate) fn parse_ansi(text: &str) -> Vec<Line> {
+pub(crate) fn parse_ansi(text: &str, tab_width: usize) -> Vec<Line> {
     let mut parser = vte::Parser::new();
-    let mut performer = AnsiPerformer::new();
+    let mut performer = AnsiPerformer::new(tab_width);
     parser.advance(&mut performer, text.as_bytes());
     performer.into_lines()
 }
@@ -10,15 +12,15 @@ pub(crate) fn parse_ansi(text: &str) -> Vec<Line> {
 struct AnsiPerformer {
     lines: Vec<Line>,
     style: TextStyle,
-    col: usize,
+    tab_width: usize,
 }
 
 impl AnsiPerformer {
-    fn new() -> Self {
+    fn new(tab_width: usize) -> Self {
         Self {
             lines: vec![Line::default()],
             style: TextStyle::default(),
-            col: 0,
+            tab_width,
         }
     }
 
@@ -30,7 +32,6 @@ impl AnsiPerformer {
     }
 
     fn push_char(&mut self, ch: char) {
-        let width = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
         let style = self.style.clone();
         let line = self.current_line_mut();
         if let Some(last) = line.spans.last_mut() {
@@ -48,25 +49,28 @@ impl AnsiPerformer {
                 style,
             });
         }
-        self.col += width;
     }
 
     fn new_line(&mut self) {
         self.lines.push(Line::default());
-        self.col = 0;
     }
 
+    /// Pads to the next tab stop. The column is measured on the printed
+    /// line so combining marks and emoji sequences count as whole clusters.
     fn expand_tab(&mut self) {
-        let mut count = 0;
-        while !(self.col + count).is_multiple_of(ANSI_TAB_WIDTH) {
-            count += 1;
-        }
-        if count == 0 {
-            count = ANSI_TAB_WIDTH;
-        }
-        for _ in 0..count {
+        let tab_width = self.tab_width;
+        let col = line_width_cells(self.current_line_mut(), tab_width);
+        let count = tab_stop_cells(col, tab_width);
+        let style = self.style.clone();
+        for idx in 0..count {
+            self.style.tab = if idx == 0 {
+                TabCell::Start
+            } else {
+                TabCell::Fill
+            };
             self.push_char(' ');
         }
+        self.style = style;
     }
 
     fn reset_style(&mut self) {
@@ -82,9 +86,6 @@ impl vte::Perform for AnsiPerformer {
     fn execute(&mut self, byte: u8) {
         match byte {
             b'\n' => self.new_line(),
-            b'\r' => {
-                self.col = 0;
-            }
             b'\t' => self.expand_tab(),
             _ => {}
         }
@@ -228,67 +229,78 @@ pub(crate) fn xterm_color(idx: u8) -> String {
     format!("#{rr:02X}{gg:02X}{bb:02X}")
 }
 
-pub(crate) fn wrap_ansi_lines(lines: &[Line], width: usize) -> Vec<Line> {
-    if width == 0 {
-        return lines.to_vec();
-    }
+/// Wraps styled lines at `width` cells, returning the visual rows and
+/// where each one comes from.
+pub(crate) fn wrap_ansi_lines(lines: &[Line], width: usize) -> (Vec<Line>, Vec<WrapRow>) {
     let mut out = Vec::new();
-    for line in lines {
-        out.extend(split_line_by_width(line, width));
+    let mut layout = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        for (row, mut wrap_row) in split_line_by_width(line, width) {
+            wrap_row.line = idx;
+            out.push(row);
+            layout.push(wrap_row);
+        }
     }
-    out
+    (out, layout)
 }
 
-pub(crate) fn split_line_by_width(line: &Line, width: usize) -> Vec<Line> {
-    if width == 0 {
-        return vec![line.clone()];
+pub(crate) fn split_line_by_width(line: &Line, width: usize) -> Vec<(Line, WrapRow)> {
+    let mut chars = Vec::new();
+    let mut cells = Vec::new();
+    let mut styles = Vec::new();
+    for (span_idx, span) in line.spans.iter().enumerate() {
+        for cluster in graphemes(&span.text) {
+            let width = grapheme_width(cluster);
+            chars.push((cluster, width));
+            // Redacted secrets are blanked to spaces; wrap them as one word
+            // so no cell of the block is dropped at a break.
+            cells.push((if span.style.redacted { "x" } else { cluster }, width));
+            styles.push(span_idx);
+        }
+    }
+    let mut offsets = Vec::with_capacity(chars.len() + 1);
+    let mut col = 0usize;
+    offsets.push(0);
+    for (_, w) in &chars {
+        col += w;
+        offsets.push(col);
     }
-    let mut out = Vec::new();
-    let mut current = Line::default();
-    let mut current_width = 0usize;
 
-    for span in &line.spans {
-        let mut buf = String::new();
-        for ch in span.text.chars() {
-            let w = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
-            if current_width + w > width && !current.spans.is_empty() {
-                if !buf.is_empty() {
-                    current.spans.push(Span {
-                        text: buf.clone(),
-                        style: span.style.clone(),
-                    });
-                    buf.clear();
-                }
-                out.push(current);
-                current = Line::default();
-                current_width = 0;
-            }
-            buf.push(ch);
-            current_width += w;
-            if current_width >= width {
-                current.spans.push(Span {
-                    text: buf.clone(),
-                    style: span.style.clone(),
+    let (hanging, rows) = wrap_line(&cells, width);
+    let mut out = Vec::with_capacity(rows.len());
+    for (row_idx, (start, end)) in rows.into_iter().enumerate() {
+        let indent = if row_idx > 0 { hanging } else { 0 };
+        let mut row = Line::default();
+        if indent > 0 {
+            row.spans.push(Span {
+                text: " ".repeat(indent),
+                style: TextStyle::default(),
+            });
+        }
+        let mut current: Option<usize> = None;
+        for idx in start..end {
+            let span_idx = styles[idx];
+            if current != Some(span_idx) {
+                row.spans.push(Span {
+                    text: String::new(),
+                    style: line.spans[span_idx].style.clone(),
                 });
-                buf.clear();
-                out.push(current);
-                current = Line::default();
-                current_width = 0;
+                current = Some(span_idx);
+            }
+            if let Some(last) = row.spans.last_mut() {
+                last.text.push_str(chars[idx].0);
             }
         }
-        if !buf.is_empty() {
-            current.spans.push(Span {
-                text: buf.clone(),
-                style: span.style.clone(),
-            });
-        }
-    }
-
-    if !current.spans.is_empty() {
-        out.push(current);
-    }
-    if out.is_empty() {
-        out.push(Line::default());
+        out.push((
+            row,
+            WrapRow {
+                line: 0,
+                start: offsets[start],
+                end: offsets[end],
+                indent,
+                continuation: row_idx > 0,
+            },
+        ));
     }
     out
 }
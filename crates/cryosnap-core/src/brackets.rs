@@ -0,0 +1,199 @@
+use crate::syntax::scoped_lines;
+use crate::text::WrapRow;
+use crate::{BracketOptions, Error, Line, Result, Span, TextStyle};
+use std::path::Path;
+
+const OPEN_BRACKETS: [char; 3] = ['(', '[', '{'];
+const CLOSE_BRACKETS: [char; 3] = [')', ']', '}'];
+
+#[derive(Debug, Clone, Copy)]
+struct Bracket {
+    row: usize,
+    index: usize,
+    line: usize,
+    column: usize,
+    kind: usize,
+    open: bool,
+}
+
+/// Parses a `LINE:COLUMN` position (1-based source line, 1-based cell
+/// column).
+pub(crate) fn parse_bracket_position(value: &str) -> Result<(usize, usize)> {
+    let invalid = || Error::InvalidInput(format!("invalid bracket position: {value}"));
+    let (line, column) = value.trim().split_once(':').ok_or_else(invalid)?;
+    let line = line.trim().parse::<usize>().map_err(|_| invalid())?;
+    let column = column.trim().parse::<usize>().map_err(|_| invalid())?;
+    if line == 0 || column == 0 {
+        return Err(invalid());
+    }
+    Ok((line, column))
+}
+
+/// Marks, per row and char, whether the char sits inside a string or
+/// comment scope, where brackets are not counted.
+pub(crate) fn masked_chars(
+    text: &str,
+    path: Option<&Path>,
+    language: Option<&str>,
+) -> Result<Vec<Vec<bool>>> {
+    Ok(scoped_lines(text, path, language)?
+        .into_iter()
+        .map(|tokens| {
+            tokens
+                .iter()
+                .flat_map(|token| token.text.chars().map(move |_| token.masked))
+                .collect()
+        })
+        .collect())
+}
+
+/// Colors brackets by nesting depth and emphasizes the pair at `focus`.
+/// `layout` maps each row back to its source line so `focus` can be given
+/// as a 1-based `(line, column)` relative to `line_base`.
+pub(crate) fn colorize_brackets(
+    rows: Vec<Line>,
+    masks: &[Vec<bool>],
+    layout: &[WrapRow],
+    line_base: usize,
+    options: &BracketOptions,
+    focus: Option<(usize, usize)>,
+) -> Vec<Line> {
+    let mut brackets = Vec::new();
+    for (row_idx, row) in rows.iter().enumerate() {
+        let wrap = layout.get(row_idx);
+        let mut col = 0usize;
+        let chars = row.spans.iter().flat_map(|span| span.text.chars());
+        for (index, ch) in chars.enumerate() {
+            let masked = masks
+                .get(row_idx)
+                .and_then(|mask| mask.get(index))
+                .copied()
+                .unwrap_or(false);
+            let kind = OPEN_BRACKETS
+                .iter()
+                .position(|c| *c == ch)
+                .map(|kind| (kind, true))
+                .or_else(|| {
+                    CLOSE_BRACKETS
+                        .iter()
+                        .position(|c| *c == ch)
+                        .map(|kind| (kind, false))
+                });
+            if let Some((kind, open)) = kind.filter(|_| !masked) {
+                let (line, column) = match wrap {
+                    Some(wrap) => (wrap.line, wrap.start + col.saturating_sub(wrap.indent)),
+                    None => (row_idx, col),
+                };
+                brackets.push(Bracket {
+                    row: row_idx,
+                    index,
+                    line: line_base + line + 1,
+                    column: column + 1,
+                    kind,
+                    open,
+                });
+            }
+            col += unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
+        }
+    }
+
+    // Pair brackets with a stack; a closing bracket of the wrong kind stays
+    // unmatched.
+    let mut pairs = Vec::new();
+    let mut depths = vec![None; brackets.len()];
+    let mut stack: Vec<usize> = Vec::new();
+    for (idx, bracket) in brackets.iter().enumerate() {
+        if bracket.open {
+            depths[idx] = Some(stack.len());
+            stack.push(idx);
+        } else if let Some(open) = stack
+            .last()
+            .copied()
+            .filter(|open| brackets[*open].kind == bracket.kind)
+        {
+            stack.pop();
+            depths[idx] = depths[open];
+            pairs.push((open, idx));
+        }
+    }
+    let matched = pairs
+        .iter()
+        .flat_map(|(open, close)| [*open, *close])
+        .collect::<std::collections::HashSet<_>>();
+
+    let emphasized = focus.and_then(|(line, column)| {
+        let at = |b: &Bracket| b.line == line && b.column == column;
+        pairs
+            .iter()
+            .find(|(open, close)| at(&brackets[*open]) || at(&brackets[*close]))
+            .or_else(|| {
+                pairs
+                    .iter()
+                    .filter(|(open, close)| {
+                        let start = &brackets[*open];
+                        let end = &brackets[*close];
+                        (start.line, start.column) < (line, column)
+                            && (line, column) < (end.line, end.column)
+                    })
+                    .max_by_key(|(open, _)| (brackets[*open].line, brackets[*open].column))
+            })
+            .copied()
+    });
+
+    let mut changes: Vec<Vec<(usize, Option<String>, bool)>> = vec![Vec::new(); rows.len()];
+    for (idx, bracket) in brackets.iter().enumerate() {
+        if !matched.contains(&idx) {
+            continue;
+        }
+        let color = depths[idx]
+            .filter(|_| options.rainbow && !options.palette.is_empty())
+            .map(|depth| options.palette[depth % options.palette.len()].clone());
+        let emphasis = emphasized.is_some_and(|(open, close)| idx == open || idx == close);
+        if color.is_some() || emphasis {
+            changes[bracket.row].push((bracket.index, color, emphasis));
+        }
+    }
+
+    rows.into_iter()
+        .zip(changes)
+        .map(|(row, changes)| {
+            if changes.is_empty() {
+                row
+            } else {
+                restyle_chars(row, &changes)
+            }
+        })
+        .collect()
+}
+
+fn restyle_chars(line: Line, changes: &[(usize, Option<String>, bool)]) -> Line {
+    let mut out = Line::default();
+    let mut index = 0usize;
+    for span in line.spans {
+        for ch in span.text.chars() {
+            let mut style = span.style.clone();
+            if let Some((_, color, emphasis)) = changes.iter().find(|(i, _, _)| *i == index) {
+                if let Some(color) = color {
+                    style.fg = Some(color.clone());
+                }
+                if *emphasis {
+                    style.bold = true;
+                    style.underline = true;
+                }
+            }
+            push_char(&mut out, ch, style);
+            index += 1;
+        }
+    }
+    out
+}
+
+fn push_char(line: &mut Line, ch: char, style: TextStyle) {
+    match line.spans.last_mut() {
+        Some(last) if last.style == style => last.text.push(ch),
+        _ => line.spans.push(Span {
+            text: ch.to_string(),
+            style,
+        }),
+    }
+}
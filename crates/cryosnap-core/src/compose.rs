@@ -0,0 +1,334 @@
+use std::path::{Path, PathBuf};
+
+use crate::render::{
+    load_source, render_frame_svg, render_loaded_svg, render_png_from_svg, render_webp_from_svg,
+};
+use crate::svg::escape_attr;
+use crate::{
+    BackdropStyle, Config, Error, InputSource, LayoutChrome, LayoutDirection, LayoutOptions,
+    OutputFormat, PaneSpec, RenderResult, Result, NO_COLOR,
+};
+
+/// One input of a composed image.
+#[derive(Debug, Clone)]
+pub struct Pane {
+    pub input: InputSource,
+    /// Config fields for this pane, merged over the shared config.
+    pub overrides: serde_json::Value,
+}
+
+impl Pane {
+    pub fn new(input: InputSource) -> Self {
+        Self {
+            input,
+            overrides: serde_json::Value::Null,
+        }
+    }
+}
+
+impl PaneSpec {
+    /// Resolves the spec to a pane: `execute` runs a command, `input` is a
+    /// file path or a `path@rev` git spec.
+    pub fn to_pane(&self) -> Result<Pane> {
+        let input = match (&self.execute, &self.input) {
+            (Some(cmd), _) => InputSource::Command(cmd.clone()),
+            (None, Some(input)) if Path::new(input).exists() => {
+                InputSource::File(PathBuf::from(input))
+            }
+            (None, Some(input)) => {
+                InputSource::git(input).unwrap_or_else(|| InputSource::File(PathBuf::from(input)))
+            }
+            (None, None) => {
+                return Err(Error::InvalidInput(
+                    "layout pane needs an input or execute".to_string(),
+                ))
+            }
+        };
+        Ok(Pane {
+            input,
+            overrides: self.config.clone(),
+        })
+    }
+}
+
+/// Renders each pane through the normal pipeline and lays them out in one
+/// image following `config.layout`. Panes sharing a row get the same
+/// height and panes sharing a column the same width. The composed image is
+/// rasterized with `config`'s fonts and raster options.
+pub fn render_panes(panes: &[Pane], config: &Config, format: OutputFormat) -> Result<RenderResult> {
+    let svg = compose_svg(panes, config)?;
+    let bytes = match format {
+        OutputFormat::Svg => svg,
+        OutputFormat::Png => render_png_from_svg(&svg, config)?,
+        OutputFormat::Webp => render_webp_from_svg(&svg, config)?,
+    };
+    Ok(RenderResult { format, bytes })
+}
+
+fn compose_svg(panes: &[Pane], config: &Config) -> Result<Vec<u8>> {
+    let cells = panes
+        .iter()
+        .map(|pane| Ok((pane.input.clone(), pane_config(config, &pane.overrides)?)))
+        .collect::<Result<Vec<_>>>()?;
+    compose_cells(&cells, config, &config.layout)
+}
+
+/// Renders `input` with the output of `command` stacked below it in one
+/// window, split by a divider. The command runs once, even when its pane
+/// is re-rendered to the width of the code.
+pub(crate) fn compose_run(input: &InputSource, command: &str, config: &Config) -> Result<Vec<u8>> {
+    let layout = LayoutOptions {
+        direction: LayoutDirection::Column,
+        gap: 0.0,
+        chrome: LayoutChrome::Shared,
+        divider: Some(config.run.divider.clone()),
+        ..LayoutOptions::default()
+    };
+    let code = pane_config(config, &serde_json::Value::Null)?;
+    let mut output = code.clone();
+    output.show_line_numbers = false;
+    output.gutter.blame = false;
+    output.lines = Default::default();
+    output.block = Default::default();
+    output.symbol = None;
+    let cells = [
+        (input.clone(), code),
+        (InputSource::Command(command.to_string()), output),
+    ];
+    compose_cells(&cells, config, &layout)
+}
+
+fn compose_cells(
+    cells: &[(InputSource, Config)],
+    config: &Config,
+    layout: &LayoutOptions,
+) -> Result<Vec<u8>> {
+    if cells.is_empty() {
+        return Err(Error::InvalidInput(
+            "layout needs at least one pane".to_string(),
+        ));
+    }
+    let columns = match layout.direction {
+        LayoutDirection::Row => cells.len(),
+        LayoutDirection::Column => 1,
+        LayoutDirection::Grid => layout.columns.clamp(1, cells.len()),
+    };
+    let rows = cells.len().div_ceil(columns);
+    let shared = layout.chrome == LayoutChrome::Shared;
+
+    let cells = cells
+        .iter()
+        .map(|(input, cfg)| {
+            let mut cfg = cfg.clone();
+            if shared {
+                cfg.window_controls = false;
+                cfg.background = NO_COLOR.to_string();
+                cfg.border.width = 0.0;
+                cfg.shadow = Default::default();
+            }
+            (input, cfg)
+        })
+        .collect::<Vec<_>>();
+    // Load every input once; a pane is rendered again below when its cell
+    // is larger, and that must not re-run its command.
+    let sources = cells
+        .iter()
+        .map(|(input, cfg)| load_source(input, cfg))
+        .collect::<Result<Vec<_>>>()?;
+    let ids = |idx: usize| format!("pane{idx}-");
+    let mut rendered = cells
+        .iter()
+        .zip(&sources)
+        .enumerate()
+        .map(|(idx, ((input, cfg), source))| render_loaded_svg(input, source, cfg, &ids(idx)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut col_widths = vec![0.0f32; columns];
+    let mut row_heights = vec![0.0f32; rows];
+    for (idx, svg) in rendered.iter().enumerate() {
+        let (width, height) = svg_size(svg)?;
+        col_widths[idx % columns] = col_widths[idx % columns].max(width);
+        row_heights[idx / columns] = row_heights[idx / columns].max(height);
+    }
+    for (idx, (input, cfg)) in cells.iter().enumerate() {
+        let cell = (col_widths[idx % columns], row_heights[idx / columns]);
+        if svg_size(&rendered[idx])? != cell {
+            let mut cfg = cfg.clone();
+            (cfg.width, cfg.height) = cell;
+            rendered[idx] = render_loaded_svg(input, &sources[idx], &cfg, &ids(idx))?;
+        }
+    }
+
+    let gap = layout.gap.max(0.0);
+    let inner_width = col_widths.iter().sum::<f32>() + gap * (columns - 1) as f32;
+    let inner_height = row_heights.iter().sum::<f32>() + gap * (rows - 1) as f32;
+    let mut frame = config.clone();
+    frame.show_line_numbers = false;
+    frame.whitespace.visible = false;
+    if !shared {
+        frame.window_controls = false;
+        frame.background = NO_COLOR.to_string();
+        frame.border.width = 0.0;
+        frame.shadow = Default::default();
+    }
+    // Size the empty frame through its padding so it still lays out its
+    // own margins, captions and watermark around the panes.
+    let inset = frame.border.width;
+    let line_px = frame.font.size * frame.line_height;
+    frame.padding = vec![
+        0.0,
+        inner_width + 2.0 * inset,
+        inner_height + inset - line_px,
+        0.0,
+    ];
+    (frame.width, frame.height) = (0.0, 0.0);
+    let (frame_svg, (content_x, origin_y)) = render_frame_svg(cells[0].0, &frame)?;
+    let frame_svg = String::from_utf8(frame_svg).map_err(|err| Error::Render(err.to_string()))?;
+    let origin_x = content_x + inset;
+    let close = frame_svg
+        .rfind("</svg>")
+        .ok_or_else(|| Error::Render("frame svg is not closed".to_string()))?;
+
+    let mut out = String::from(&frame_svg[..close]);
+    let mut y = origin_y;
+    for (row, height) in row_heights.iter().enumerate() {
+        let mut x = origin_x;
+        for (col, width) in col_widths.iter().enumerate() {
+            if let Some(svg) = rendered.get(row * columns + col) {
+                out.push_str(&format!(
+                    r#"<g transform="translate({:.2} {:.2})">{}</g>"#,
+                    x,
+                    y,
+                    svg_body(svg)?
+                ));
+            }
+            x += width + gap;
+        }
+        y += height + gap;
+    }
+    if let Some(color) = layout.divider.as_deref() {
+        out.push_str(&dividers_svg(
+            color,
+            (origin_x, origin_y),
+            &col_widths,
+            &row_heights,
+            gap,
+            cells.len(),
+        ));
+    }
+    out.push_str("</svg>");
+    Ok(out.into_bytes())
+}
+
+/// Lines centered in the gaps between rows and between the columns of
+/// each row.
+fn dividers_svg(
+    color: &str,
+    (origin_x, origin_y): (f32, f32),
+    col_widths: &[f32],
+    row_heights: &[f32],
+    gap: f32,
+    count: usize,
+) -> String {
+    let columns = col_widths.len();
+    let full_width = col_widths.iter().sum::<f32>() + gap * (columns - 1) as f32;
+    let mut lines = String::new();
+    let mut y = origin_y;
+    for (row, height) in row_heights.iter().enumerate() {
+        let mut x = origin_x;
+        let in_row = count.saturating_sub(row * columns).min(columns);
+        for width in col_widths.iter().take(in_row.saturating_sub(1)) {
+            x += width + gap / 2.0;
+            lines.push_str(&format!(
+                r#"<line x1="{x:.2}" y1="{:.2}" x2="{x:.2}" y2="{:.2}" stroke="{}" stroke-width="1" />"#,
+                y,
+                y + height,
+                escape_attr(color)
+            ));
+            x += gap / 2.0;
+        }
+        y += height + gap;
+        if row + 1 < row_heights.len() {
+            let line_y = y - gap / 2.0;
+            lines.push_str(&format!(
+                r#"<line x1="{:.2}" y1="{line_y:.2}" x2="{:.2}" y2="{line_y:.2}" stroke="{}" stroke-width="1" />"#,
+                origin_x,
+                origin_x + full_width,
+                escape_attr(color)
+            ));
+        }
+    }
+    lines
+}
+
+/// The shared config with the pane's overrides applied. The composition
+/// owns margins and the backdrop.
+fn pane_config(config: &Config, overrides: &serde_json::Value) -> Result<Config> {
+    let mut cfg = if overrides.is_null() {
+        config.clone()
+    } else {
+        let mut value = serde_json::to_value(config)
+            .map_err(|err| Error::InvalidInput(format!("pane config: {err}")))?;
+        merge_json(&mut value, overrides);
+        serde_json::from_value(value)
+            .map_err(|err| Error::InvalidInput(format!("pane config: {err}")))?
+    };
+    cfg.margin = vec![0.0];
+    cfg.backdrop.style = BackdropStyle::None;
+    cfg.run.command = None;
+    cfg.caption.text = None;
+    cfg.footer = Default::default();
+    cfg.watermark.text = None;
+    cfg.watermark.image = None;
+    Ok(cfg)
+}
+
+fn merge_json(base: &mut serde_json::Value, patch: &serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch.clone(),
+    }
+}
+
+fn svg_size(svg: &[u8]) -> Result<(f32, f32)> {
+    let text = std::str::from_utf8(svg).map_err(|err| Error::Render(err.to_string()))?;
+    let tag = &text[svg_open_tag(text)?];
+    let attr = |name: &str| {
+        let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+        let end = tag[start..].find('"')? + start;
+        tag[start..end].parse::<f32>().ok()
+    };
+    match (attr("width"), attr("height")) {
+        (Some(width), Some(height)) => Ok((width, height)),
+        _ => Err(Error::Render("svg has no size".to_string())),
+    }
+}
+
+/// Byte range of the root `<svg ...>` start tag.
+fn svg_open_tag(svg: &str) -> Result<std::ops::Range<usize>> {
+    let start = svg
+        .find("<svg")
+        .ok_or_else(|| Error::Render("missing svg element".to_string()))?;
+    let end = svg[start..]
+        .find('>')
+        .ok_or_else(|| Error::Render("unterminated svg element".to_string()))?;
+    Ok(start..start + end + 1)
+}
+
+/// Everything between the root element's start and end tags.
+fn svg_body(svg: &[u8]) -> Result<&str> {
+    let text = std::str::from_utf8(svg).map_err(|err| Error::Render(err.to_string()))?;
+    let start = svg_open_tag(text)?.end;
+    let end = text
+        .rfind("</svg>")
+        .ok_or_else(|| Error::Render("svg is not closed".to_string()))?;
+    Ok(&text[start..end])
+}
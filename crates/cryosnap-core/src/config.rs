@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    DEFAULT_PNG_OPT_LEVEL, DEFAULT_PNG_QUANTIZE_DITHER, DEFAULT_PNG_QUANTIZE_QUALITY,
-    DEFAULT_PNG_QUANTIZE_SPEED, DEFAULT_RASTER_MAX_PIXELS, DEFAULT_RASTER_SCALE,
-    DEFAULT_TITLE_MAX_WIDTH, DEFAULT_TITLE_OPACITY, DEFAULT_TITLE_SIZE,
+    AUTO_COLOR, DEFAULT_BRACKET_PALETTE, DEFAULT_PNG_OPT_LEVEL, DEFAULT_PNG_QUANTIZE_DITHER,
+    DEFAULT_PNG_QUANTIZE_QUALITY, DEFAULT_PNG_QUANTIZE_SPEED, DEFAULT_RASTER_MAX_PIXELS,
+    DEFAULT_RASTER_SCALE, DEFAULT_REDACT_ENTROPY_MIN_LENGTH, DEFAULT_REDACT_ENTROPY_THRESHOLD,
+    DEFAULT_TITLE_MAX_WIDTH, DEFAULT_TITLE_OPACITY, DEFAULT_TITLE_SIZE, NO_COLOR,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,45 +20,88 @@ pub struct Config {
     pub height: f32,
     #[serde(rename = "window")]
     pub window_controls: bool,
+    /// Look of the title bar drawn when `window` is on.
+    pub chrome: ChromeOptions,
     #[serde(rename = "show_line_numbers")]
     pub show_line_numbers: bool,
     pub language: Option<String>,
     pub execute_timeout_ms: u64,
     pub wrap: usize,
+    pub tab_width: Option<usize>,
     #[serde(deserialize_with = "deserialize_lines")]
-    pub lines: Vec<i32>,
+    pub lines: LineSelection,
+    pub elision: String,
+    pub dedent: DedentOptions,
+    pub symbol: Option<String>,
     pub border: Border,
     pub shadow: Shadow,
+    /// Fill for the margin area around the window.
+    pub backdrop: Backdrop,
     pub font: Font,
     #[serde(rename = "line_height")]
     pub line_height: f32,
+    /// Outlines all text as paths so the SVG does not depend on the
+    /// viewer's fonts.
+    pub text_to_path: bool,
     pub raster: RasterOptions,
     pub png: PngOptions,
     pub title: TitleOptions,
+    pub gutter: GutterOptions,
+    pub whitespace: WhitespaceOptions,
+    pub brackets: BracketOptions,
+    pub block: BlockOptions,
+    pub redact: RedactOptions,
+    /// Arrangement for images composed from several panes.
+    pub layout: LayoutOptions,
+    /// Command run alongside the input, shown below the code.
+    pub run: RunOptions,
+    /// Text under the window.
+    pub caption: CaptionOptions,
+    /// Author, URL and date line under the caption.
+    pub footer: FooterOptions,
+    /// Text or logo in a corner of the image.
+    pub watermark: WatermarkOptions,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             theme: "charm".to_string(),
-            background: "#171717".to_string(),
+            background: AUTO_COLOR.to_string(),
             padding: vec![20.0, 40.0, 20.0, 20.0],
             margin: vec![0.0],
             width: 0.0,
             height: 0.0,
             window_controls: false,
+            chrome: ChromeOptions::default(),
             show_line_numbers: false,
             language: None,
             execute_timeout_ms: 10_000,
             wrap: 0,
-            lines: vec![0, -1],
+            tab_width: None,
+            lines: LineSelection::default(),
+            elision: "⋮".to_string(),
+            dedent: DedentOptions::default(),
+            symbol: None,
             border: Border::default(),
             shadow: Shadow::default(),
+            backdrop: Backdrop::default(),
             font: Font::default(),
             line_height: 1.2,
+            text_to_path: false,
             raster: RasterOptions::default(),
             png: PngOptions::default(),
             title: TitleOptions::default(),
+            gutter: GutterOptions::default(),
+            whitespace: WhitespaceOptions::default(),
+            brackets: BracketOptions::default(),
+            block: BlockOptions::default(),
+            redact: RedactOptions::default(),
+            layout: LayoutOptions::default(),
+            run: RunOptions::default(),
+            caption: CaptionOptions::default(),
+            footer: FooterOptions::default(),
+            watermark: WatermarkOptions::default(),
         }
     }
 }
@@ -98,13 +142,103 @@ impl Default for Shadow {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ChromeOptions {
+    pub style: ChromeStyle,
+    /// Shows the title, and `extra_tabs`, as a tab strip.
+    pub tabs: bool,
+    /// File paths shown as further tabs after the input's own.
+    pub extra_tabs: Vec<String>,
+    /// 0-based index of the highlighted tab.
+    pub active_tab: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChromeStyle {
+    #[default]
+    Macos,
+    Windows,
+    Gnome,
+    Minimal,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Backdrop {
+    pub style: BackdropStyle,
+    /// Gradient stops in order. Solid and pattern styles use the first
+    /// color as the fill and the second as the pattern ink.
+    pub colors: Vec<String>,
+    /// Linear gradient direction in degrees, clockwise from "to top".
+    pub angle: f32,
+    /// PNG or JPEG file for the image style.
+    pub image: Option<String>,
+    pub fit: BackdropFit,
+    /// Pattern cell size in pixels.
+    pub size: f32,
+}
+
+impl Default for Backdrop {
+    fn default() -> Self {
+        Self {
+            style: BackdropStyle::None,
+            colors: vec!["#4F46E5".to_string(), "#DB2777".to_string()],
+            angle: 135.0,
+            image: None,
+            fit: BackdropFit::Cover,
+            size: 16.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackdropStyle {
+    #[default]
+    None,
+    Solid,
+    Linear,
+    Radial,
+    Image,
+    Dots,
+    Grid,
+    Noise,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackdropFit {
+    #[default]
+    Cover,
+    Contain,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Font {
     pub family: String,
     pub file: Option<String>,
+    /// Bold and italic faces of `family`; `file` is the regular face.
+    pub files: FontFiles,
     pub size: f32,
     pub ligatures: bool,
+    /// Positions every grapheme on its cell so fallback glyphs whose
+    /// advance differs from the primary font do not shift later text.
+    pub grid: bool,
+    /// OpenType features such as `ss01`, `-calt` or `cv02=2`, applied by
+    /// SVG viewers and the rsvg backend; resvg ignores them and
+    /// `text_to_path` rejects them.
+    pub features: Vec<String>,
+    /// Embeds only the glyphs the image uses, plus the glyphs its
+    /// substitutions can produce. Variable and color fonts stay whole.
+    pub subset: bool,
+    /// Embeds fonts as WOFF2 instead of their source format.
+    pub woff2: bool,
+    /// Embeds subsets of the fallback fonts that draw glyphs in the SVG.
+    pub embed_fallbacks: bool,
     pub fallbacks: Vec<String>,
     #[serde(rename = "system_fallback")]
     pub system_fallback: FontSystemFallback,
@@ -123,8 +257,14 @@ impl Default for Font {
         Self {
             family: "monospace".to_string(),
             file: None,
+            files: FontFiles::default(),
             size: 14.0,
             ligatures: true,
+            grid: false,
+            features: Vec::new(),
+            subset: true,
+            woff2: false,
+            embed_fallbacks: false,
             fallbacks: Vec::new(),
             system_fallback: FontSystemFallback::default(),
             auto_download: true,
@@ -135,6 +275,31 @@ impl Default for Font {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct FontFiles {
+    pub bold: Option<String>,
+    pub italic: Option<String>,
+    #[serde(rename = "bold_italic")]
+    pub bold_italic: Option<String>,
+}
+
+impl Font {
+    /// Font files with the weight and italic flag they provide, regular
+    /// face first.
+    pub(crate) fn faces(&self) -> Vec<(&str, u16, bool)> {
+        [
+            (&self.file, 400, false),
+            (&self.files.bold, 700, false),
+            (&self.files.italic, 400, true),
+            (&self.files.bold_italic, 700, true),
+        ]
+        .into_iter()
+        .filter_map(|(file, weight, italic)| Some((file.as_deref()?, weight, italic)))
+        .collect()
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum FontSystemFallback {
@@ -192,6 +357,104 @@ pub enum TitleAlign {
     Right,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionOptions {
+    /// Caption text; `\n` starts a new line.
+    pub text: Option<String>,
+    pub align: TitleAlign,
+    /// Font family; the code font when unset.
+    pub family: Option<String>,
+    /// Font size; 0 uses the code font size.
+    pub size: f32,
+    pub color: String,
+    pub opacity: f32,
+}
+
+impl Default for CaptionOptions {
+    fn default() -> Self {
+        Self {
+            text: None,
+            align: TitleAlign::Center,
+            family: None,
+            size: 0.0,
+            color: "#A0A0A0".to_string(),
+            opacity: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FooterOptions {
+    pub author: Option<String>,
+    pub url: Option<String>,
+    /// Date text, or `today` for the current date as YYYY-MM-DD.
+    pub date: Option<String>,
+    /// Placed between the author, URL and date.
+    pub separator: String,
+    pub align: TitleAlign,
+    pub family: Option<String>,
+    /// Font size; 0 uses the title size.
+    pub size: f32,
+    pub color: String,
+    pub opacity: f32,
+}
+
+impl Default for FooterOptions {
+    fn default() -> Self {
+        Self {
+            author: None,
+            url: None,
+            date: None,
+            separator: " · ".to_string(),
+            align: TitleAlign::Center,
+            family: None,
+            size: 0.0,
+            color: "#808080".to_string(),
+            opacity: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatermarkOptions {
+    pub text: Option<String>,
+    /// Logo image (PNG or JPEG), drawn before the text.
+    pub image: Option<String>,
+    pub position: WatermarkPosition,
+    pub family: Option<String>,
+    /// Text size, and logo height; 0 uses the title size.
+    pub size: f32,
+    pub color: String,
+    pub opacity: f32,
+}
+
+impl Default for WatermarkOptions {
+    fn default() -> Self {
+        Self {
+            text: None,
+            image: None,
+            position: WatermarkPosition::BottomRight,
+            family: None,
+            size: 0.0,
+            color: "#808080".to_string(),
+            opacity: 0.6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum TitlePathStyle {
@@ -225,7 +488,7 @@ impl Default for TitleOptions {
             tmux_format: "#{session_name}:#{window_index}.#{pane_index} #{pane_title}".to_string(),
             align: TitleAlign::Center,
             size: DEFAULT_TITLE_SIZE,
-            color: "#C5C8C6".to_string(),
+            color: AUTO_COLOR.to_string(),
             opacity: DEFAULT_TITLE_OPACITY,
             max_width: DEFAULT_TITLE_MAX_WIDTH,
             ellipsis: "…".to_string(),
@@ -233,6 +496,241 @@ impl Default for TitleOptions {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GutterOptions {
+    pub color: String,
+    pub blame: bool,
+    pub start: Option<usize>,
+    pub min_digits: usize,
+    pub separator: String,
+    pub separator_line: bool,
+    pub background: String,
+    pub align: GutterAlign,
+    pub relative: bool,
+    pub focus: Option<usize>,
+    pub focus_color: String,
+}
+
+impl Default for GutterOptions {
+    fn default() -> Self {
+        Self {
+            color: AUTO_COLOR.to_string(),
+            blame: false,
+            start: None,
+            min_digits: 3,
+            separator: "  ".to_string(),
+            separator_line: false,
+            background: NO_COLOR.to_string(),
+            align: GutterAlign::default(),
+            relative: false,
+            focus: None,
+            focus_color: AUTO_COLOR.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GutterAlign {
+    Left,
+    #[default]
+    Right,
+}
+
+/// Bracket pair colorization. `match_at` is a `LINE:COLUMN` position whose
+/// bracket pair (or innermost enclosing pair) is emphasized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BracketOptions {
+    pub rainbow: bool,
+    pub palette: Vec<String>,
+    pub match_at: Option<String>,
+}
+
+impl Default for BracketOptions {
+    fn default() -> Self {
+        Self {
+            rainbow: false,
+            palette: DEFAULT_BRACKET_PALETTE
+                .iter()
+                .map(|color| color.to_string())
+                .collect(),
+            match_at: None,
+        }
+    }
+}
+
+/// Strips the indentation shared by the selected lines, then indents them
+/// by `indent` spaces.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct DedentOptions {
+    pub enabled: bool,
+    pub indent: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WhitespaceOptions {
+    pub visible: bool,
+    pub indent_guides: bool,
+    pub color: String,
+}
+
+impl Default for WhitespaceOptions {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            indent_guides: false,
+            color: AUTO_COLOR.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    #[default]
+    Auto,
+    Markdown,
+    Rst,
+    Ipynb,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BlockOptions {
+    pub index: Option<usize>,
+    pub label: Option<String>,
+    pub heading: Option<String>,
+    pub format: DocumentFormat,
+}
+
+impl BlockOptions {
+    pub fn is_active(&self) -> bool {
+        self.index.is_some() || self.label.is_some() || self.heading.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactOptions {
+    pub patterns: Vec<String>,
+    pub builtin: bool,
+    pub entropy: bool,
+    pub entropy_threshold: f32,
+    pub entropy_min_length: usize,
+    pub style: RedactStyle,
+    pub color: String,
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            builtin: false,
+            entropy: false,
+            entropy_threshold: DEFAULT_REDACT_ENTROPY_THRESHOLD,
+            entropy_min_length: DEFAULT_REDACT_ENTROPY_MIN_LENGTH,
+            style: RedactStyle::Solid,
+            color: AUTO_COLOR.to_string(),
+        }
+    }
+}
+
+impl RedactOptions {
+    pub fn is_active(&self) -> bool {
+        !self.patterns.is_empty() || self.builtin || self.entropy
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutOptions {
+    pub direction: LayoutDirection,
+    /// Panes per row for the grid direction.
+    pub columns: usize,
+    /// Space between panes in pixels.
+    pub gap: f32,
+    pub chrome: LayoutChrome,
+    /// Color of lines drawn between panes.
+    pub divider: Option<String>,
+    /// Panes shown after the main input.
+    pub panes: Vec<PaneSpec>,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            direction: LayoutDirection::Row,
+            columns: 2,
+            gap: 24.0,
+            chrome: LayoutChrome::Separate,
+            divider: None,
+            panes: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutDirection {
+    /// Side by side.
+    #[default]
+    Row,
+    /// Stacked top to bottom.
+    Column,
+    Grid,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutChrome {
+    /// Every pane is its own window.
+    #[default]
+    Separate,
+    /// One window around all panes.
+    Shared,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PaneSpec {
+    /// File path or `path@rev` git spec.
+    pub input: Option<String>,
+    /// Command whose output fills the pane.
+    pub execute: Option<String>,
+    /// Config fields that differ for this pane, merged over the top level.
+    pub config: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunOptions {
+    /// Command whose ANSI output is shown under the code, in the same window.
+    pub command: Option<String>,
+    /// Color of the line between the code and the output.
+    pub divider: String,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            command: None,
+            divider: "#515151".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactStyle {
+    #[default]
+    Solid,
+    Blur,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum PngStrip {
@@ -337,7 +835,103 @@ fn parse_box_string(input: &str) -> std::result::Result<Vec<f32>, String> {
     }
 }
 
-fn deserialize_lines<'de, D>(deserializer: D) -> std::result::Result<Vec<i32>, D::Error>
+/// Which lines of the input to capture.
+///
+/// `Window` keeps the original `start,end` form (0-based, inclusive). `Ranges`
+/// holds one or more 1-based ranges such as `1-5,40-60` or `/fn main/../^}/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineSelection {
+    Window(Vec<i32>),
+    Ranges(Vec<LineRange>),
+}
+
+impl Default for LineSelection {
+    fn default() -> Self {
+        LineSelection::Window(vec![0, -1])
+    }
+}
+
+impl From<Vec<i32>> for LineSelection {
+    fn from(window: Vec<i32>) -> Self {
+        LineSelection::Window(window)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: Option<LineBound>,
+    pub end: Option<LineBound>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineBound {
+    /// 1-based line number; negative values count from the end.
+    Number(i32),
+    /// First line matching the regular expression.
+    Pattern(String),
+}
+
+impl std::fmt::Display for LineBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineBound::Number(n) => write!(f, "{}", n),
+            LineBound::Pattern(p) => write!(f, "/{}/", p.replace('/', "\\/")),
+        }
+    }
+}
+
+impl std::fmt::Display for LineRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(start) = self.start.as_ref().filter(|_| self.start == self.end) {
+            return write!(f, "{}", start);
+        }
+        if let Some(start) = &self.start {
+            write!(f, "{}", start)?;
+        }
+        f.write_str("..")?;
+        if let Some(end) = &self.end {
+            write!(f, "{}", end)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for LineSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineSelection::Window(window) => {
+                let parts = window.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                f.write_str(&parts.join(","))
+            }
+            LineSelection::Ranges(ranges) => {
+                let parts = ranges.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+                f.write_str(&parts.join(","))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for LineSelection {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        parse_lines_string(input)
+    }
+}
+
+impl Serialize for LineSelection {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            LineSelection::Window(window) => window.serialize(serializer),
+            LineSelection::Ranges(_) => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+fn deserialize_lines<'de, D>(deserializer: D) -> std::result::Result<LineSelection, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -345,53 +939,146 @@ where
     parse_lines_value(&value).map_err(serde::de::Error::custom)
 }
 
-fn parse_lines_value(value: &serde_json::Value) -> std::result::Result<Vec<i32>, String> {
+fn parse_lines_value(value: &serde_json::Value) -> std::result::Result<LineSelection, String> {
     match value {
         serde_json::Value::Number(n) => n
             .as_i64()
-            .map(|v| vec![v as i32])
+            .map(|v| LineSelection::Window(vec![v as i32]))
             .ok_or_else(|| "invalid number".to_string()),
         serde_json::Value::String(s) => parse_lines_string(s),
         serde_json::Value::Array(arr) => {
-            let mut out = Vec::new();
+            let mut parts = Vec::new();
             for item in arr {
                 match item {
                     serde_json::Value::Number(n) => {
-                        out.push(n.as_i64().ok_or_else(|| "invalid number".to_string())? as i32);
-                    }
-                    serde_json::Value::String(s) => {
-                        let parsed = parse_lines_string(s)?;
-                        out.extend(parsed);
+                        let value = n.as_i64().ok_or_else(|| "invalid number".to_string())?;
+                        parts.push(value.to_string());
                     }
+                    serde_json::Value::String(s) => parts.push(s.clone()),
                     _ => return Err("invalid array value".to_string()),
                 }
             }
-            if matches!(out.len(), 1 | 2) {
-                Ok(out)
-            } else {
-                Err(format!("expected 1 or 2 values, got {}", out.len()))
-            }
+            parse_lines_string(&parts.join(","))
         }
-        serde_json::Value::Null => Ok(vec![]),
+        serde_json::Value::Null => Ok(LineSelection::Window(vec![])),
         _ => Err("invalid lines value".to_string()),
     }
 }
 
-fn parse_lines_string(input: &str) -> std::result::Result<Vec<i32>, String> {
-    let parts: Vec<&str> = input.split([',', ' ']).filter(|s| !s.is_empty()).collect();
-    if parts.is_empty() {
-        return Ok(vec![]);
+fn split_line_specs(input: &str) -> std::result::Result<Vec<String>, String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_pattern = false;
+    let mut chars = input.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if in_pattern => {
+                current.push(ch);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '/' => {
+                in_pattern = !in_pattern;
+                current.push(ch);
+            }
+            ',' | ' ' if !in_pattern => {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(ch),
+        }
     }
-    let mut out = Vec::new();
-    for part in parts {
-        let value = part
-            .parse::<i32>()
-            .map_err(|_| format!("invalid number {}", part))?;
-        out.push(value);
+    if in_pattern {
+        return Err(format!("unterminated pattern in {}", input));
     }
-    if matches!(out.len(), 1 | 2) {
-        Ok(out)
-    } else {
-        Err(format!("expected 1 or 2 values, got {}", out.len()))
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    Ok(parts)
+}
+
+fn parse_line_bound(input: &str) -> std::result::Result<Option<LineBound>, String> {
+    if input.is_empty() {
+        return Ok(None);
+    }
+    if let Some(body) = input
+        .strip_prefix('/')
+        .and_then(|rest| rest.strip_suffix('/'))
+    {
+        if body.is_empty() {
+            return Err("empty line pattern".to_string());
+        }
+        return Ok(Some(LineBound::Pattern(body.replace("\\/", "/"))));
+    }
+    input
+        .parse::<i32>()
+        .map(|n| Some(LineBound::Number(n)))
+        .map_err(|_| format!("invalid line {}", input))
+}
+
+/// Finds the `..` separator outside of `/pattern/` bounds.
+fn find_range_separator(input: &str) -> Option<usize> {
+    let bytes = input.as_bytes();
+    let mut in_pattern = false;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'\\' if in_pattern => idx += 1,
+            b'/' => in_pattern = !in_pattern,
+            b'.' if !in_pattern && bytes.get(idx + 1) == Some(&b'.') => return Some(idx),
+            _ => {}
+        }
+        idx += 1;
+    }
+    None
+}
+
+fn parse_line_range(input: &str) -> std::result::Result<LineRange, String> {
+    if let Some(idx) = find_range_separator(input) {
+        return Ok(LineRange {
+            start: parse_line_bound(&input[..idx])?,
+            end: parse_line_bound(&input[idx + 2..])?,
+        });
+    }
+    if let Some((start, end)) = input.split_once('-') {
+        if let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) {
+            return Ok(LineRange {
+                start: Some(LineBound::Number(start as i32)),
+                end: Some(LineBound::Number(end as i32)),
+            });
+        }
+    }
+    let bound = parse_line_bound(input)?;
+    if bound.is_none() {
+        return Err(format!("invalid line range {}", input));
+    }
+    Ok(LineRange {
+        start: bound.clone(),
+        end: bound,
+    })
+}
+
+fn parse_lines_string(input: &str) -> std::result::Result<LineSelection, String> {
+    let parts = split_line_specs(input)?;
+    if parts.is_empty() {
+        return Ok(LineSelection::Window(vec![]));
+    }
+    let numbers = parts
+        .iter()
+        .map(|part| part.parse::<i32>())
+        .collect::<std::result::Result<Vec<_>, _>>();
+    if let Ok(window) = numbers {
+        return if matches!(window.len(), 1 | 2) {
+            Ok(LineSelection::Window(window))
+        } else {
+            Err(format!("expected 1 or 2 values, got {}", window.len()))
+        };
     }
+    let ranges = parts
+        .iter()
+        .map(|part| parse_line_range(part))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(LineSelection::Ranges(ranges))
 }
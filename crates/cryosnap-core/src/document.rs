@@ -0,0 +1,389 @@
+use crate::input::{InputKind, LoadedInput};
+use crate::{BlockOptions, DocumentFormat, Error, Result};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CodeBlock {
+    pub(crate) language: Option<String>,
+    pub(crate) labels: Vec<String>,
+    pub(crate) heading: Option<String>,
+    pub(crate) text: String,
+    pub(crate) start: usize,
+}
+
+pub(crate) fn select_code_block(
+    loaded: LoadedInput,
+    options: &BlockOptions,
+) -> Result<LoadedInput> {
+    if !options.is_active() {
+        return Ok(loaded);
+    }
+    let format = match options.format {
+        DocumentFormat::Auto => detect_document_format(loaded.path.as_deref()),
+        other => other,
+    };
+    let blocks = match format {
+        DocumentFormat::Rst => parse_rst_blocks(&loaded.text),
+        DocumentFormat::Ipynb => parse_ipynb_blocks(&loaded.text)?,
+        DocumentFormat::Auto | DocumentFormat::Markdown => parse_markdown_blocks(&loaded.text),
+    };
+    let block = pick_code_block(blocks, options)?;
+    Ok(LoadedInput {
+        text: block.text,
+        path: loaded.path,
+        kind: InputKind::Code,
+        language: block.language,
+        line_offset: loaded.line_offset + block.start,
+    })
+}
+
+pub(crate) fn detect_document_format(path: Option<&Path>) -> DocumentFormat {
+    let ext = path
+        .and_then(|path| path.extension())
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "rst" | "rest" => DocumentFormat::Rst,
+        "ipynb" => DocumentFormat::Ipynb,
+        _ => DocumentFormat::Markdown,
+    }
+}
+
+pub(crate) fn pick_code_block(blocks: Vec<CodeBlock>, options: &BlockOptions) -> Result<CodeBlock> {
+    let label = options
+        .label
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    let heading = options
+        .heading
+        .as_deref()
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty());
+    let mut matches = blocks
+        .into_iter()
+        .filter(|block| match label {
+            Some(label) => block.labels.iter().any(|v| v.eq_ignore_ascii_case(label)),
+            None => true,
+        })
+        .filter(|block| match &heading {
+            Some(heading) => block
+                .heading
+                .as_ref()
+                .is_some_and(|v| v.to_lowercase().contains(heading)),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+    let index = options.index.unwrap_or(1);
+    if index == 0 {
+        return Err(Error::InvalidInput("block index starts at 1".to_string()));
+    }
+    if index > matches.len() {
+        return Err(Error::InvalidInput(format!(
+            "code block {} not found ({} matching)",
+            index,
+            matches.len()
+        )));
+    }
+    Ok(matches.swap_remove(index - 1))
+}
+
+pub(crate) fn parse_info_string(info: &str) -> (Option<String>, Vec<String>) {
+    let cleaned = info.replace(['{', '}', ','], " ");
+    let mut language = None;
+    let mut labels = Vec::new();
+    for token in cleaned.split_whitespace() {
+        let token = token.trim_start_matches('.').trim_start_matches('#');
+        if token.is_empty() {
+            continue;
+        }
+        let value = match token.split_once('=') {
+            Some((_, value)) => value.trim_matches(['"', '\'']),
+            None => {
+                if language.is_none() {
+                    language = Some(token.to_string());
+                }
+                token
+            }
+        };
+        if !value.is_empty() {
+            labels.push(value.to_string());
+        }
+    }
+    (language, labels)
+}
+
+fn atx_heading(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim();
+    Some(text.to_string())
+}
+
+fn is_setext_underline(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'))
+}
+
+fn opening_fence(line: &str) -> Option<(usize, char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    let indent = line.len() - trimmed.len();
+    if indent > 3 {
+        return None;
+    }
+    let fence_char = trimmed.chars().next()?;
+    if fence_char != '`' && fence_char != '~' {
+        return None;
+    }
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    if len < 3 {
+        return None;
+    }
+    let info = trimmed[len..].trim();
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    Some((indent, fence_char, len, info))
+}
+
+fn is_closing_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    }
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    len >= fence_len && trimmed[len..].trim().is_empty()
+}
+
+fn strip_indent(line: &str, indent: usize) -> &str {
+    let spaces = line.len() - line.trim_start_matches(' ').len();
+    &line[spaces.min(indent)..]
+}
+
+pub(crate) fn parse_markdown_blocks(text: &str) -> Vec<CodeBlock> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut blocks = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx].trim_end_matches('\r');
+        if let Some((indent, fence_char, fence_len, info)) = opening_fence(line) {
+            let start = idx + 1;
+            let mut end = start;
+            while end < lines.len()
+                && !is_closing_fence(lines[end].trim_end_matches('\r'), fence_char, fence_len)
+            {
+                end += 1;
+            }
+            let body = lines[start..end]
+                .iter()
+                .map(|line| strip_indent(line.trim_end_matches('\r'), indent))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let (language, labels) = parse_info_string(info);
+            blocks.push(CodeBlock {
+                language,
+                labels,
+                heading: heading.clone(),
+                text: body,
+                start,
+            });
+            idx = end + 1;
+            continue;
+        }
+        if let Some(text) = atx_heading(line) {
+            heading = Some(text);
+        } else if !line.trim().is_empty()
+            && idx + 1 < lines.len()
+            && is_setext_underline(lines[idx + 1].trim_end_matches('\r'))
+            && !line.trim_start().starts_with(['-', '*', '>'])
+        {
+            heading = Some(line.trim().to_string());
+            idx += 1;
+        }
+        idx += 1;
+    }
+    blocks
+}
+
+const RST_CODE_DIRECTIVES: &[&str] = &["code-block", "sourcecode", "code"];
+
+fn rst_directive(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix("..")?.trim_start();
+    let (name, arg) = rest.split_once("::")?;
+    let name = name.trim();
+    if RST_CODE_DIRECTIVES.contains(&name) {
+        Some(arg.trim())
+    } else {
+        None
+    }
+}
+
+fn is_rst_adornment(line: &str) -> bool {
+    let trimmed = line.trim_end();
+    let mut chars = trimmed.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    first.is_ascii_punctuation() && trimmed.len() >= 3 && chars.all(|c| c == first)
+}
+
+fn indent_width(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+pub(crate) fn parse_rst_blocks(text: &str) -> Vec<CodeBlock> {
+    let lines: Vec<&str> = text.split('\n').map(|l| l.trim_end_matches('\r')).collect();
+    let mut blocks = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        if let Some(arg) = rst_directive(line) {
+            let directive_indent = indent_width(line);
+            let mut labels = Vec::new();
+            let language = arg.split_whitespace().next().map(|v| v.to_string());
+            if let Some(lang) = &language {
+                labels.push(lang.clone());
+            }
+            let mut cursor = idx + 1;
+            while cursor < lines.len() {
+                let option = lines[cursor].trim();
+                if !option.starts_with(':') || indent_width(lines[cursor]) <= directive_indent {
+                    break;
+                }
+                if let Some((key, value)) = option[1..].split_once(':') {
+                    let value = value.trim();
+                    if matches!(key.trim(), "name" | "caption") && !value.is_empty() {
+                        labels.push(value.to_string());
+                    }
+                }
+                cursor += 1;
+            }
+            while cursor < lines.len() && lines[cursor].trim().is_empty() {
+                cursor += 1;
+            }
+            let start = cursor;
+            let mut end = cursor;
+            let mut body_indent = usize::MAX;
+            while end < lines.len() {
+                let current = lines[end];
+                if !current.trim().is_empty() {
+                    let indent = indent_width(current);
+                    if indent <= directive_indent {
+                        break;
+                    }
+                    body_indent = body_indent.min(indent);
+                }
+                end += 1;
+            }
+            let mut body_end = end;
+            while body_end > start && lines[body_end - 1].trim().is_empty() {
+                body_end -= 1;
+            }
+            let body_indent = if body_indent == usize::MAX {
+                0
+            } else {
+                body_indent
+            };
+            let body = lines[start..body_end]
+                .iter()
+                .map(|line| line.get(body_indent..).unwrap_or("").trim_end())
+                .collect::<Vec<_>>()
+                .join("\n");
+            blocks.push(CodeBlock {
+                language,
+                labels,
+                heading: heading.clone(),
+                text: body,
+                start,
+            });
+            idx = end;
+            continue;
+        }
+        let title = line.trim();
+        if !title.is_empty()
+            && !is_rst_adornment(line)
+            && idx + 1 < lines.len()
+            && is_rst_adornment(lines[idx + 1])
+            && lines[idx + 1].trim_end().len() >= title.chars().count()
+        {
+            heading = Some(title.to_string());
+            idx += 2;
+            continue;
+        }
+        idx += 1;
+    }
+    blocks
+}
+
+fn notebook_source(value: Option<&serde_json::Value>) -> String {
+    match value {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.as_str())
+            .collect::<String>(),
+        _ => String::new(),
+    }
+}
+
+/// Code cells keep their own numbering from line 1, the way Jupyter shows
+/// them, rather than a position in the notebook JSON.
+pub(crate) fn parse_ipynb_blocks(text: &str) -> Result<Vec<CodeBlock>> {
+    let notebook: serde_json::Value = serde_json::from_str(text)
+        .map_err(|err| Error::InvalidInput(format!("notebook parse: {err}")))?;
+    let metadata = &notebook["metadata"];
+    let language = metadata["language_info"]["name"]
+        .as_str()
+        .or_else(|| metadata["kernelspec"]["language"].as_str())
+        .map(|v| v.to_string());
+    let Some(cells) = notebook["cells"].as_array() else {
+        return Err(Error::InvalidInput("notebook has no cells".to_string()));
+    };
+    let mut blocks = Vec::new();
+    let mut heading: Option<String> = None;
+    for cell in cells {
+        let source = notebook_source(cell.get("source"));
+        match cell["cell_type"].as_str() {
+            Some("markdown") => {
+                if let Some(last) = source.split('\n').rev().find_map(atx_heading) {
+                    heading = Some(last);
+                }
+            }
+            Some("code") => {
+                let mut labels = Vec::new();
+                if let Some(tags) = cell["metadata"]["tags"].as_array() {
+                    labels.extend(tags.iter().filter_map(|v| v.as_str()).map(str::to_string));
+                }
+                if let Some(id) = cell["id"].as_str() {
+                    labels.push(id.to_string());
+                }
+                if let Some(lang) = &language {
+                    labels.push(lang.clone());
+                }
+                let text = source.strip_suffix('\n').unwrap_or(&source).to_string();
+                blocks.push(CodeBlock {
+                    language: language.clone(),
+                    labels,
+                    heading: heading.clone(),
+                    text,
+                    start: 0,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(blocks)
+}
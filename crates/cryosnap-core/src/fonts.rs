@@ -2,6 +2,8 @@ mod cjk;
 mod constants;
 pub(crate) mod dirs;
 mod download;
+mod embed;
+mod metrics;
 mod models;
 mod plan;
 mod scan;
@@ -10,6 +12,8 @@ mod system;
 pub(crate) use cjk::*;
 pub(crate) use constants::*;
 pub(crate) use download::*;
+pub(crate) use embed::*;
+pub(crate) use metrics::*;
 pub(crate) use models::*;
 pub(crate) use plan::*;
 pub(crate) use scan::*;
@@ -0,0 +1,370 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+
+use super::system::is_generic_family;
+
+const SFNT_VERSIONS: [&[u8; 4]; 3] = [b"\x00\x01\x00\x00", b"OTTO", b"true"];
+const WOFF2_ARBITRARY_TAG: u8 = 63;
+const WOFF2_NULL_GLYF_TRANSFORM: u8 = 3 << 6;
+/// Tables copied into subsets as they are; they address glyphs by id.
+const LAYOUT_TABLES: [&[u8; 4]; 3] = [b"GDEF", b"GPOS", b"GSUB"];
+
+/// Reduces an OpenType font to the glyphs that draw `chars`, keeping glyph
+/// ids stable. Every glyph GSUB can substitute in is kept as well, and the
+/// layout tables are copied over unchanged so ligatures and `font.features`
+/// still apply. Returns `None` when the data is not a font the subsetter can
+/// read, e.g. WOFF input.
+pub(crate) fn subset_font(data: &[u8], index: u32, chars: &BTreeSet<char>) -> Option<Vec<u8>> {
+    let face = ttf_parser::Face::parse(data, index).ok()?;
+    let mut glyphs = BTreeSet::from([0u16]);
+    glyphs.extend(
+        chars
+            .iter()
+            .filter_map(|ch| face.glyph_index(*ch))
+            .map(|glyph| glyph.0),
+    );
+    if let Some(gsub) = face.tables().gsub {
+        substitution_closure(&gsub, &mut glyphs);
+    }
+    let glyphs = glyphs.into_iter().collect::<Vec<_>>();
+    let subset = subsetter::subset(data, index, subsetter::Profile::pdf(&glyphs)).ok()?;
+
+    let raw = face.raw_face();
+    let layout = LAYOUT_TABLES
+        .iter()
+        .filter_map(|tag| Some((&tag[..], raw.table(ttf_parser::Tag::from_bytes(tag))?)))
+        .collect::<Vec<_>>();
+    if layout.is_empty() {
+        return Some(subset);
+    }
+    let mut tables = sfnt_tables(&subset)?;
+    tables.extend(layout);
+    Some(write_sfnt(&subset[0..4], tables))
+}
+
+/// Adds every glyph a GSUB lookup can produce from `glyphs` until nothing
+/// new turns up. Contextual lookups only call other lookups, which are
+/// visited on their own, so they add nothing here.
+fn substitution_closure(
+    gsub: &ttf_parser::opentype_layout::LayoutTable,
+    glyphs: &mut BTreeSet<u16>,
+) {
+    use ttf_parser::gsub::{SingleSubstitution, SubstitutionSubtable};
+
+    loop {
+        let mut found = Vec::new();
+        for lookup in gsub.lookups {
+            for subtable in lookup.subtables.into_iter::<SubstitutionSubtable>() {
+                let coverage = subtable.coverage();
+                for &glyph in glyphs.iter() {
+                    let Some(idx) = coverage.get(ttf_parser::GlyphId(glyph)) else {
+                        continue;
+                    };
+                    match subtable {
+                        SubstitutionSubtable::Single(SingleSubstitution::Format1 {
+                            delta, ..
+                        }) => {
+                            found.push(glyph.wrapping_add_signed(delta));
+                        }
+                        SubstitutionSubtable::Single(SingleSubstitution::Format2 {
+                            substitutes,
+                            ..
+                        }) => found.extend(substitutes.get(idx).map(|glyph| glyph.0)),
+                        SubstitutionSubtable::Multiple(table) => {
+                            if let Some(sequence) = table.sequences.get(idx) {
+                                found.extend(sequence.substitutes.into_iter().map(|glyph| glyph.0));
+                            }
+                        }
+                        SubstitutionSubtable::Alternate(table) => {
+                            if let Some(set) = table.alternate_sets.get(idx) {
+                                found.extend(set.alternates.into_iter().map(|glyph| glyph.0));
+                            }
+                        }
+                        SubstitutionSubtable::Ligature(table) => {
+                            for ligature in table.ligature_sets.get(idx).into_iter().flatten() {
+                                if ligature
+                                    .components
+                                    .into_iter()
+                                    .all(|glyph| glyphs.contains(&glyph.0))
+                                {
+                                    found.push(ligature.glyph.0);
+                                }
+                            }
+                        }
+                        SubstitutionSubtable::ReverseChainSingle(table) => {
+                            found.extend(table.substitutes.get(idx).map(|glyph| glyph.0));
+                        }
+                        SubstitutionSubtable::Context(_)
+                        | SubstitutionSubtable::ChainContext(_) => {}
+                    }
+                }
+            }
+        }
+        let before = glyphs.len();
+        glyphs.extend(found);
+        if glyphs.len() == before {
+            return;
+        }
+    }
+}
+
+/// A font from the database together with the characters it ends up
+/// drawing in the image.
+#[derive(Debug, Clone)]
+pub(crate) struct FallbackFace {
+    pub(crate) family: String,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Resolves each `(families, chars)` group the way a browser walks a
+/// `font-family` list: every character goes to the first named family in
+/// the database with a glyph for it. Each family that draws something is
+/// returned once, prepared by [`embed_face`]. Generic families and the names in `skip` are never embedded.
+pub(crate) fn fallback_faces(
+    fontdb: &usvg::fontdb::Database,
+    groups: &[(Vec<String>, BTreeSet<char>)],
+    skip: &[&str],
+) -> Vec<FallbackFace> {
+    let mut ids = HashMap::new();
+    let mut used: Vec<(String, usvg::fontdb::ID, BTreeSet<char>)> = Vec::new();
+    for (families, chars) in groups {
+        for ch in chars {
+            let owner = families.iter().find_map(|family| {
+                if is_generic_family(family)
+                    || skip.iter().any(|name| name.eq_ignore_ascii_case(family))
+                {
+                    return None;
+                }
+                let id = ids
+                    .entry(family.to_ascii_lowercase())
+                    .or_insert_with(|| query_family(fontdb, family))
+                    .to_owned()?;
+                fontdb
+                    .with_face_data(id, |data, index| {
+                        ttf_parser::Face::parse(data, index)
+                            .ok()
+                            .and_then(|face| face.glyph_index(*ch))
+                            .is_some()
+                    })
+                    .unwrap_or(false)
+                    .then_some((family, id))
+            });
+            let Some((family, id)) = owner else {
+                continue;
+            };
+            match used.iter_mut().find(|(_, used_id, _)| *used_id == id) {
+                Some((_, _, used_chars)) => {
+                    used_chars.insert(*ch);
+                }
+                None => used.push((family.clone(), id, BTreeSet::from([*ch]))),
+            }
+        }
+    }
+
+    used.into_iter()
+        .filter_map(|(family, id, chars)| {
+            let data =
+                fontdb.with_face_data(id, |data, index| embed_face(data, index, &chars))??;
+            Some(FallbackFace { family, data })
+        })
+        .collect()
+}
+
+/// Prepares face `index` of `data` for embedding. Color faces (bitmap, COLR
+/// or SVG glyphs) and variable faces are copied whole, even out of a
+/// collection, because the subsetter drops those tables; other faces are
+/// subset to `chars`.
+pub(crate) fn embed_face(data: &[u8], index: u32, chars: &BTreeSet<char>) -> Option<Vec<u8>> {
+    let face = ttf_parser::Face::parse(data, index).ok()?;
+    let tables = face.tables();
+    let whole = tables.cbdt.is_some()
+        || tables.sbix.is_some()
+        || tables.colr.is_some()
+        || tables.svg.is_some()
+        || tables.fvar.is_some();
+    if !whole {
+        return subset_font(data, index, chars);
+    }
+    let raw = face.raw_face();
+    let records = raw
+        .table_records
+        .into_iter()
+        .map(|record| (record.tag.to_bytes(), record))
+        .collect::<Vec<_>>();
+    let tables = records
+        .iter()
+        .map(|(tag, record)| {
+            let start = record.offset as usize;
+            let data = raw
+                .data
+                .get(start..start.checked_add(record.length as usize)?)?;
+            Some((&tag[..], data))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let version = if tables
+        .iter()
+        .any(|(tag, _)| *tag == b"CFF " || *tag == b"CFF2")
+    {
+        SFNT_VERSIONS[1]
+    } else {
+        SFNT_VERSIONS[0]
+    };
+    Some(write_sfnt(version, tables))
+}
+
+fn query_family(fontdb: &usvg::fontdb::Database, family: &str) -> Option<usvg::fontdb::ID> {
+    let families = [usvg::fontdb::Family::Name(family)];
+    fontdb.query(&usvg::fontdb::Query {
+        families: &families,
+        ..usvg::fontdb::Query::default()
+    })
+}
+
+/// Wraps an sfnt font in a WOFF2 container. Every table is stored with the
+/// null transform and all table data shares one Brotli stream.
+pub(crate) fn encode_woff2(sfnt: &[u8]) -> Option<Vec<u8>> {
+    let flavor = sfnt.get(0..4)?;
+    if !SFNT_VERSIONS.iter().any(|version| version[..] == *flavor) {
+        return None;
+    }
+    let mut tables = sfnt_tables(sfnt)?;
+    // WOFF2 requires `loca` to directly follow `glyf`.
+    tables.sort_by_key(|(tag, _)| *tag);
+    let loca = tables
+        .iter()
+        .position(|(tag, _)| *tag == b"loca")
+        .map(|pos| tables.remove(pos));
+    if let Some(loca) = loca {
+        let glyf = tables.iter().position(|(tag, _)| *tag == b"glyf")?;
+        tables.insert(glyf + 1, loca);
+    }
+
+    let mut directory = Vec::new();
+    let mut stream = Vec::new();
+    let mut sfnt_size = 12 + 16 * tables.len();
+    for (tag, data) in &tables {
+        let transform = if *tag == b"glyf" || *tag == b"loca" {
+            WOFF2_NULL_GLYF_TRANSFORM
+        } else {
+            0
+        };
+        directory.push(WOFF2_ARBITRARY_TAG | transform);
+        directory.extend_from_slice(tag);
+        write_base128(&mut directory, data.len() as u32);
+        stream.extend_from_slice(data);
+        sfnt_size += (data.len() + 3) & !3;
+    }
+    let mut compressed = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+        writer.write_all(&stream).ok()?;
+    }
+
+    let mut out = Vec::with_capacity(48 + directory.len() + compressed.len() + 3);
+    out.extend_from_slice(b"wOF2");
+    out.extend_from_slice(flavor);
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    out.extend_from_slice(&[0; 2]);
+    out.extend_from_slice(&(sfnt_size as u32).to_be_bytes());
+    out.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+    out.extend_from_slice(&[0, 1, 0, 0]);
+    out.extend_from_slice(&[0; 20]);
+    out.extend_from_slice(&directory);
+    out.extend_from_slice(&compressed);
+    while out.len() % 4 != 0 {
+        out.push(0);
+    }
+    let length = (out.len() as u32).to_be_bytes();
+    out[8..12].copy_from_slice(&length);
+    Some(out)
+}
+
+/// Reads the `(tag, data)` pairs from an sfnt table directory.
+pub(crate) fn sfnt_tables(sfnt: &[u8]) -> Option<Vec<(&[u8], &[u8])>> {
+    let num_tables = read_u16(sfnt, 4)? as usize;
+    let mut tables = Vec::with_capacity(num_tables);
+    for idx in 0..num_tables {
+        let record = 12 + idx * 16;
+        let tag = sfnt.get(record..record + 4)?;
+        let offset = read_u32(sfnt, record + 8)? as usize;
+        let length = read_u32(sfnt, record + 12)? as usize;
+        let data = sfnt.get(offset..offset.checked_add(length)?)?;
+        tables.push((tag, data));
+    }
+    Some(tables)
+}
+
+/// Writes an sfnt with the given tables, sorted by tag and 4-byte aligned,
+/// and fixes up the checksums including `head.checkSumAdjustment`.
+pub(crate) fn write_sfnt(version: &[u8], mut tables: Vec<(&[u8], &[u8])>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| *tag);
+    tables.dedup_by_key(|(tag, _)| *tag);
+    let count = tables.len() as u16;
+    let selector = 15 - count.max(1).leading_zeros() as u16;
+    let search_range = (1u16 << selector) * 16;
+    let mut out = Vec::new();
+    out.extend_from_slice(version);
+    out.extend_from_slice(&count.to_be_bytes());
+    out.extend_from_slice(&search_range.to_be_bytes());
+    out.extend_from_slice(&selector.to_be_bytes());
+    out.extend_from_slice(&(count * 16 - search_range).to_be_bytes());
+
+    let mut body = Vec::new();
+    let mut head_offset = None;
+    let body_start = 12 + 16 * tables.len();
+    for (tag, data) in &tables {
+        let offset = body_start + body.len();
+        let mut data = data.to_vec();
+        if *tag == b"head" && data.len() >= 12 {
+            data[8..12].fill(0);
+            head_offset = Some(offset);
+        }
+        out.extend_from_slice(tag);
+        out.extend_from_slice(&sfnt_checksum(&data).to_be_bytes());
+        out.extend_from_slice(&(offset as u32).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        body.extend_from_slice(&data);
+        while body.len() % 4 != 0 {
+            body.push(0);
+        }
+    }
+    out.extend_from_slice(&body);
+    if let Some(offset) = head_offset {
+        let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(sfnt_checksum(&out));
+        out[offset + 8..offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    out
+}
+
+fn sfnt_checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Writes a WOFF2 `UIntBase128`: big-endian groups of 7 bits, with the high
+/// bit set on every byte but the last.
+fn write_base128(out: &mut Vec<u8>, value: u32) {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        bytes.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+    }
+    out.extend(bytes.into_iter().rev());
+}
@@ -0,0 +1,109 @@
+use super::system::build_fontdb;
+use crate::svg::parse_font_family_list;
+use crate::{Config, FONT_HEIGHT_TO_WIDTH_RATIO};
+
+/// Horizontal and vertical metrics of the primary font, in ems.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FontMetrics {
+    /// Advance width of one monospace cell.
+    pub(crate) advance: f32,
+    pub(crate) ascent: f32,
+    pub(crate) descent: f32,
+}
+
+impl Default for FontMetrics {
+    fn default() -> Self {
+        Self {
+            advance: 1.0 / FONT_HEIGHT_TO_WIDTH_RATIO,
+            ascent: 0.8,
+            descent: 0.2,
+        }
+    }
+}
+
+impl FontMetrics {
+    /// Reads the metrics of a parsed face, using the advance of `0` as the
+    /// cell width. Returns `None` for faces without usable metrics.
+    pub(crate) fn from_face(face: &ttf_parser::Face) -> Option<Self> {
+        let units = f32::from(face.units_per_em());
+        if units <= 0.0 {
+            return None;
+        }
+        let advance = face
+            .glyph_index('0')
+            .or_else(|| face.glyph_index('M'))
+            .and_then(|glyph| face.glyph_hor_advance(glyph))
+            .filter(|advance| *advance > 0)?;
+        let ascent = f32::from(face.ascender()) / units;
+        let descent = -f32::from(face.descender()) / units;
+        if ascent <= 0.0 || descent < 0.0 {
+            return None;
+        }
+        Some(Self {
+            advance: f32::from(advance) / units,
+            ascent,
+            descent,
+        })
+    }
+
+    /// Offset from the top of a line box of `line_height` to the baseline,
+    /// splitting the leading evenly above and below the glyphs like CSS.
+    pub(crate) fn baseline(&self, font_size: f32, line_height: f32) -> f32 {
+        let glyphs = (self.ascent + self.descent) * font_size;
+        (line_height - glyphs) / 2.0 + self.ascent * font_size
+    }
+}
+
+/// Resolves metrics from `font.file` when set, otherwise from the first
+/// family of the `font.family` list found in the font database. Falls back to the
+/// fixed height-to-width ratio when the font cannot be read.
+pub(crate) fn resolve_font_metrics(config: &Config, needs_system_fonts: bool) -> FontMetrics {
+    if let Some(file) = &config.font.file {
+        return std::fs::read(file)
+            .ok()
+            .and_then(|data| {
+                ttf_parser::Face::parse(&data, 0)
+                    .ok()
+                    .and_then(|face| FontMetrics::from_face(&face))
+            })
+            .unwrap_or_default();
+    }
+    let Ok(fontdb) = build_fontdb(config, needs_system_fonts) else {
+        return FontMetrics::default();
+    };
+    let names = parse_font_family_list(&config.font.family);
+    let families = names
+        .iter()
+        .map(|name| {
+            let name = name.trim_matches(|c| c == '"' || c == '\'');
+            generic_family(name).unwrap_or(usvg::fontdb::Family::Name(name))
+        })
+        .collect::<Vec<_>>();
+    let query = usvg::fontdb::Query {
+        families: &families,
+        ..usvg::fontdb::Query::default()
+    };
+    fontdb
+        .query(&query)
+        .and_then(|id| {
+            fontdb.with_face_data(id, |data, index| {
+                ttf_parser::Face::parse(data, index)
+                    .ok()
+                    .and_then(|face| FontMetrics::from_face(&face))
+            })
+        })
+        .flatten()
+        .unwrap_or_default()
+}
+
+fn generic_family(name: &str) -> Option<usvg::fontdb::Family<'static>> {
+    use usvg::fontdb::Family;
+    match name.to_ascii_lowercase().as_str() {
+        "monospace" => Some(Family::Monospace),
+        "serif" => Some(Family::Serif),
+        "sans-serif" | "sans" => Some(Family::SansSerif),
+        "cursive" => Some(Family::Cursive),
+        "fantasy" => Some(Family::Fantasy),
+        _ => None,
+    }
+}
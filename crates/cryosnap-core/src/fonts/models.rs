@@ -1,3 +1,4 @@
+use super::metrics::FontMetrics;
 use std::collections::HashSet;
 use unicode_script::Script;
 
@@ -14,6 +15,7 @@ pub(crate) struct FontFallbackNeeds {
 pub(crate) struct FontPlan {
     pub(crate) font_family: String,
     pub(crate) needs_system_fonts: bool,
+    pub(crate) metrics: FontMetrics,
 }
 
 #[derive(Debug, Default, Clone)]
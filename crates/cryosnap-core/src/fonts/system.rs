@@ -2,6 +2,7 @@ use super::constants::{
     AUTO_FALLBACK_CJK, AUTO_FALLBACK_EMOJI, AUTO_FALLBACK_GLOBAL, AUTO_FALLBACK_NF,
 };
 use super::dirs::resolve_font_dirs;
+use super::metrics::FontMetrics;
 use super::models::{FontFallbackNeeds, FontPlan};
 use crate::{Config, FontSystemFallback, Result};
 use once_cell::sync::Lazy;
@@ -34,7 +35,7 @@ pub(crate) fn fontdb_build_miss_count_for_config(
     };
     let key = FontDbCacheKey {
         dirs_key: dirs_cache_key(&dirs),
-        font_file: config.font.file.as_ref().map(|v| font_file_key(v)),
+        font_files: font_file_keys(config),
         needs_system_fonts,
     };
     *FONTDB_BUILD_MISSES
@@ -54,7 +55,7 @@ struct FontFileKey {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct FontDbCacheKey {
     dirs_key: String,
-    font_file: Option<FontFileKey>,
+    font_files: Vec<FontFileKey>,
     needs_system_fonts: bool,
 }
 
@@ -94,6 +95,15 @@ fn font_file_key(path: &str) -> FontFileKey {
     }
 }
 
+fn font_file_keys(config: &Config) -> Vec<FontFileKey> {
+    config
+        .font
+        .faces()
+        .into_iter()
+        .map(|(path, _, _)| font_file_key(path))
+        .collect()
+}
+
 fn fontdb_cache_get(key: &FontDbCacheKey) -> Option<usvg::fontdb::Database> {
     let mut cache = FONT_CACHE.lock().expect("font cache lock");
     let pos = cache.fontdb.iter().position(|(k, _)| k == key)?;
@@ -214,12 +224,12 @@ pub(crate) fn needs_system_fonts(
         FontSystemFallback::Auto => {}
     }
     let mut needs = false;
-    if config.font.file.is_none() && family_requires_system(&config.font.family, app_families) {
+    if config.font.faces().is_empty() && family_requires_system(&config.font.family, app_families) {
         needs = true;
     }
     if !needs {
         for name in families {
-            if config.font.file.is_some() && name.eq_ignore_ascii_case(&config.font.family) {
+            if !config.font.faces().is_empty() && name.eq_ignore_ascii_case(&config.font.family) {
                 continue;
             }
             if family_requires_system(name, app_families) {
@@ -243,6 +253,7 @@ pub(crate) fn build_font_plan(
     FontPlan {
         font_family,
         needs_system_fonts,
+        metrics: FontMetrics::default(),
     }
 }
 
@@ -252,10 +263,9 @@ pub(crate) fn build_fontdb(
 ) -> Result<usvg::fontdb::Database> {
     let dirs = resolve_font_dirs(config)?;
     let dirs_key = dirs_cache_key(&dirs);
-    let font_file = config.font.file.as_ref().map(|v| font_file_key(v));
     let key = FontDbCacheKey {
         dirs_key,
-        font_file,
+        font_files: font_file_keys(config),
         needs_system_fonts,
     };
     if let Some(db) = fontdb_cache_get(&key) {
@@ -269,7 +279,7 @@ pub(crate) fn build_fontdb(
     }
 
     let mut fontdb = usvg::fontdb::Database::new();
-    if let Some(font_file) = &config.font.file {
+    for (font_file, _, _) in config.font.faces() {
         let bytes = std::fs::read(font_file)?;
         fontdb.load_font_data(bytes);
     }
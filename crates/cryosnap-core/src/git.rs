@@ -0,0 +1,196 @@
+use crate::{Error, InputSource, Result};
+use git2::{BlameOptions, Repository};
+use std::path::{Path, PathBuf};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const BLAME_COMMIT_LEN: usize = 7;
+const BLAME_AUTHOR_MAX_WIDTH: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlameLine {
+    pub(crate) commit: String,
+    pub(crate) author: String,
+    pub(crate) time: i64,
+    pub(crate) committed: bool,
+}
+
+fn git_error(err: git2::Error) -> Error {
+    Error::InvalidInput(format!("git: {}", err.message()))
+}
+
+/// Opens the repository containing `path` and returns it with the
+/// repository-relative path of the file.
+fn open_repository(path: &Path) -> Result<(Repository, PathBuf)> {
+    let absolute = std::path::absolute(path)?;
+    let parent = absolute.parent().unwrap_or(Path::new("/"));
+    let parent = parent
+        .canonicalize()
+        .unwrap_or_else(|_| parent.to_path_buf());
+    let file_name = absolute
+        .file_name()
+        .ok_or_else(|| Error::InvalidInput(format!("not a file: {}", path.display())))?;
+    let repo = Repository::discover(&parent).map_err(git_error)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| Error::InvalidInput("git: bare repositories are not supported".into()))?;
+    let workdir = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_path_buf());
+    let relative = parent
+        .join(file_name)
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .map_err(|_| {
+            Error::InvalidInput(format!("{} is outside the repository", path.display()))
+        })?;
+    Ok((repo, relative))
+}
+
+pub(crate) fn read_file_at_revision(path: &Path, rev: &str) -> Result<String> {
+    let (repo, relative) = open_repository(path)?;
+    let commit = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(git_error)?;
+    let blob = commit
+        .tree()
+        .and_then(|tree| tree.get_path(&relative))
+        .and_then(|entry| entry.to_object(&repo))
+        .and_then(|object| object.peel_to_blob())
+        .map_err(git_error)?;
+    String::from_utf8(blob.content().to_vec())
+        .map_err(|_| Error::InvalidInput(format!("{}@{} is not valid UTF-8", path.display(), rev)))
+}
+
+/// Blames every line of a file input. For working tree files, `text` is
+/// blamed as a buffer so uncommitted lines are reported as such.
+pub(crate) fn blame_input(input: &InputSource, text: &str) -> Result<Vec<BlameLine>> {
+    let (path, rev) = match input {
+        InputSource::File(path) => (path, None),
+        InputSource::Git { path, rev } => (path, Some(rev.as_str())),
+        _ => {
+            return Err(Error::InvalidInput(
+                "blame requires a file or git revision input".to_string(),
+            ))
+        }
+    };
+    let (repo, relative) = open_repository(path)?;
+    let mut options = BlameOptions::new();
+    if let Some(rev) = rev {
+        let commit = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(git_error)?;
+        options.newest_commit(commit.id());
+    }
+    let blame = repo
+        .blame_file(&relative, Some(&mut options))
+        .map_err(git_error)?;
+    let blame = match rev {
+        Some(_) => blame,
+        None => blame.blame_buffer(text.as_bytes()).map_err(git_error)?,
+    };
+
+    let line_count = text.split('\n').count();
+    let mut lines = Vec::with_capacity(line_count);
+    for lineno in 1..=line_count {
+        let line = match blame.get_line(lineno) {
+            Some(hunk) if !hunk.final_commit_id().is_zero() => {
+                let signature = hunk.final_signature();
+                let mut commit = hunk.final_commit_id().to_string();
+                commit.truncate(BLAME_COMMIT_LEN);
+                BlameLine {
+                    commit,
+                    author: signature.name().unwrap_or("").to_string(),
+                    time: signature.when().seconds(),
+                    committed: true,
+                }
+            }
+            _ => BlameLine {
+                commit: "0".repeat(BLAME_COMMIT_LEN),
+                author: "uncommitted".to_string(),
+                time: 0,
+                committed: false,
+            },
+        };
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+pub(crate) fn format_age(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+    let seconds = seconds.max(0);
+    match seconds {
+        s if s < MINUTE => "now".to_string(),
+        s if s < HOUR => format!("{}m", s / MINUTE),
+        s if s < DAY => format!("{}h", s / HOUR),
+        s if s < MONTH => format!("{}d", s / DAY),
+        s if s < YEAR => format!("{}mo", s / MONTH),
+        s => format!("{}y", s / YEAR),
+    }
+}
+
+fn fit_width(text: &str, width: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let w = ch.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        out.push(ch);
+        used += w;
+    }
+    out.push_str(&" ".repeat(width - used));
+    out
+}
+
+/// Builds the blame column for each rendered row. `numbers` holds the
+/// 1-based source line of each row; consecutive rows from the same commit
+/// only annotate the first one.
+pub(crate) fn blame_annotations(
+    blame: &[BlameLine],
+    numbers: &[Option<usize>],
+    now: i64,
+) -> Vec<Option<String>> {
+    let rows = numbers
+        .iter()
+        .map(|number| number.and_then(|n| blame.get(n.checked_sub(1)?)))
+        .collect::<Vec<_>>();
+    let author_width = rows
+        .iter()
+        .flatten()
+        .map(|line| line.author.width())
+        .max()
+        .unwrap_or(0)
+        .min(BLAME_AUTHOR_MAX_WIDTH);
+    let mut previous: Option<&str> = None;
+    rows.into_iter()
+        .map(|row| {
+            let Some(line) = row else {
+                previous = None;
+                return None;
+            };
+            if previous == Some(line.commit.as_str()) {
+                return Some(String::new());
+            }
+            previous = Some(line.commit.as_str());
+            let age = if line.committed {
+                format_age(now - line.time)
+            } else {
+                String::new()
+            };
+            Some(format!(
+                "{} {} {:>3}",
+                line.commit,
+                fit_width(&line.author, author_width),
+                age
+            ))
+        })
+        .collect()
+}
@@ -1,3 +1,4 @@
+use crate::git::read_file_at_revision;
 use crate::{Config, Error, InputSource, Result};
 use std::path::PathBuf;
 use std::time::Duration;
@@ -7,6 +8,8 @@ pub(crate) struct LoadedInput {
     pub(crate) text: String,
     pub(crate) path: Option<PathBuf>,
     pub(crate) kind: InputKind,
+    pub(crate) language: Option<String>,
+    pub(crate) line_offset: usize,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -21,6 +24,8 @@ pub(crate) fn load_input(input: &InputSource, timeout: Duration) -> Result<Loade
             text: text.clone(),
             path: None,
             kind: InputKind::Code,
+            language: None,
+            line_offset: 0,
         }),
         InputSource::File(path) => {
             let text = std::fs::read_to_string(path)?;
@@ -28,21 +33,32 @@ pub(crate) fn load_input(input: &InputSource, timeout: Duration) -> Result<Loade
                 text,
                 path: Some(path.clone()),
                 kind: InputKind::Code,
+                language: None,
+                line_offset: 0,
             })
         }
+        InputSource::Git { path, rev } => Ok(LoadedInput {
+            text: read_file_at_revision(path, rev)?,
+            path: Some(path.clone()),
+            kind: InputKind::Code,
+            language: None,
+            line_offset: 0,
+        }),
         InputSource::Command(cmd) => {
             let text = execute_command(cmd, timeout)?;
             Ok(LoadedInput {
                 text,
                 path: None,
                 kind: InputKind::Ansi,
+                language: None,
+                line_offset: 0,
             })
         }
     }
 }
 
 pub(crate) fn is_ansi_input(loaded: &LoadedInput, config: &Config) -> bool {
-    if let Some(lang) = &config.language {
+    if let Some(lang) = config.language.as_ref().or(loaded.language.as_ref()) {
         if lang.eq_ignore_ascii_case("ansi") {
             return true;
         }
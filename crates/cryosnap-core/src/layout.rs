@@ -1,4 +1,5 @@
 use crate::{Error, Line, Result, DEFAULT_TAB_WIDTH};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub(crate) fn expand_box(values: &[f32]) -> [f32; 4] {
     match values.len() {
@@ -10,15 +11,33 @@ pub(crate) fn expand_box(values: &[f32]) -> [f32; 4] {
 }
 
 pub(crate) fn text_width_cells(text: &str) -> usize {
-    let mut width = 0usize;
-    for ch in text.chars() {
-        if ch == '\t' {
-            width += DEFAULT_TAB_WIDTH;
-        } else {
-            width += unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
-        }
+    graphemes(text).fold(0usize, |col, cluster| {
+        advance_cells(col, cluster, DEFAULT_TAB_WIDTH)
+    })
+}
+
+/// Extended grapheme clusters of `text`, the unit that occupies cells.
+pub(crate) fn graphemes(text: &str) -> unicode_segmentation::Graphemes<'_> {
+    text.graphemes(true)
+}
+
+/// Terminal cell width of one grapheme cluster. Emoji presentation
+/// (`U+FE0F`) and flag pairs take two cells and text presentation
+/// (`U+FE0E`) one; otherwise the widest char decides, so combining marks,
+/// skin tones and ZWJ-joined emoji add nothing to their base.
+pub(crate) fn grapheme_width(cluster: &str) -> usize {
+    let regional = |ch: char| ('\u{1F1E6}'..='\u{1F1FF}').contains(&ch);
+    if cluster.contains('\u{FE0E}') {
+        return 1;
     }
-    width
+    if cluster.contains('\u{FE0F}') || cluster.chars().filter(|ch| regional(*ch)).count() >= 2 {
+        return 2;
+    }
+    cluster
+        .chars()
+        .map(|ch| unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0))
+        .max()
+        .unwrap_or(0)
 }
 
 pub(crate) fn truncate_to_cells(text: &str, max_cells: usize, ellipsis: &str) -> String {
@@ -31,62 +50,86 @@ pub(crate) fn truncate_to_cells(text: &str, max_cells: usize, ellipsis: &str) ->
     }
     let ellipsis_width = text_width_cells(ellipsis);
     if ellipsis_width >= max_cells {
-        return ellipsis.chars().take(1).collect();
+        return graphemes(ellipsis).take(1).collect();
     }
     let mut out = String::new();
     let mut current = 0usize;
-    for ch in text.chars() {
-        let w = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
+    for cluster in graphemes(text) {
+        let w = grapheme_width(cluster);
         if current + w > max_cells - ellipsis_width {
             break;
         }
-        out.push(ch);
+        out.push_str(cluster);
         current += w;
     }
     out.push_str(ellipsis);
     out
 }
 
-pub(crate) fn line_width_cells(line: &Line) -> usize {
+/// Number of cells a tab at `col` spans to reach the next tab stop.
+pub(crate) fn tab_stop_cells(col: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    tab_width - col % tab_width
+}
+
+/// Advances a cell column past a grapheme cluster, expanding tabs to the
+/// next tab stop.
+pub(crate) fn advance_cells(col: usize, cluster: &str, tab_width: usize) -> usize {
+    if cluster == "\t" {
+        col + tab_stop_cells(col, tab_width)
+    } else {
+        col + grapheme_width(cluster)
+    }
+}
+
+pub(crate) fn line_width_cells(line: &Line, tab_width: usize) -> usize {
     let mut width = 0usize;
     for span in &line.spans {
-        for ch in span.text.chars() {
-            if ch == '\t' {
-                let mut count = 0;
-                while !(width + count).is_multiple_of(DEFAULT_TAB_WIDTH) {
-                    count += 1;
-                }
-                if count == 0 {
-                    count = DEFAULT_TAB_WIDTH;
-                }
-                width += count;
-            } else {
-                width += unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
-            }
+        for cluster in graphemes(&span.text) {
+            width = advance_cells(width, cluster, tab_width);
         }
     }
     width
 }
 
-pub(crate) fn span_width_px(text: &str, char_width: f32) -> f32 {
+/// Cell width of the leading whitespace, or `None` for blank lines.
+pub(crate) fn leading_indent_cells(line: &Line, tab_width: usize) -> Option<usize> {
     let mut width = 0usize;
-    for ch in text.chars() {
-        if ch == '\t' {
-            let mut count = 0;
-            while !(width + count).is_multiple_of(DEFAULT_TAB_WIDTH) {
-                count += 1;
-            }
-            if count == 0 {
-                count = DEFAULT_TAB_WIDTH;
+    for span in &line.spans {
+        for cluster in graphemes(&span.text) {
+            if !cluster.chars().all(char::is_whitespace) {
+                return Some(width);
             }
-            width += count;
-        } else {
-            width += unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
+            width = advance_cells(width, cluster, tab_width);
         }
     }
+    None
+}
+
+pub(crate) fn span_width_px(text: &str, char_width: f32, tab_width: usize) -> f32 {
+    let width = graphemes(text).fold(0usize, |col, cluster| {
+        advance_cells(col, cluster, tab_width)
+    });
     width as f32 * char_width
 }
 
+/// Splits text into grapheme clusters, each paired with the cell column
+/// it starts at.
+pub(crate) fn cell_clusters(
+    text: &str,
+    start_col: usize,
+    tab_width: usize,
+) -> Vec<(String, usize)> {
+    let mut col = start_col;
+    graphemes(text)
+        .map(|cluster| {
+            let start = col;
+            col = advance_cells(col, cluster, tab_width);
+            (cluster.to_string(), start)
+        })
+        .collect()
+}
+
 pub(crate) fn scale_dimension(value: u32, scale: f32) -> Result<u32> {
     let scaled = (value as f32 * scale).round();
     if !scaled.is_finite() || scaled <= 0.0 {
@@ -1,9 +1,14 @@
 const FONT_HEIGHT_TO_WIDTH_RATIO: f32 = 1.68;
 const DEFAULT_TAB_WIDTH: usize = 4;
-const ANSI_TAB_WIDTH: usize = 6;
+const ANSI_TAB_WIDTH: usize = 8;
+const WIDE_TAB_WIDTH: usize = 8;
+const WIDE_TAB_SYNTAXES: &[&str] = &["Go", "Makefile"];
 const WINDOW_CONTROLS_HEIGHT: f32 = 18.0;
 const WINDOW_CONTROLS_X_OFFSET: f32 = 12.0;
 const WINDOW_CONTROLS_SPACING: f32 = 19.0;
+const WINDOW_TAB_BAR_HEIGHT: f32 = 30.0;
+const WINDOW_TAB_PADDING: f32 = 12.0;
+const ANNOTATION_GAP: f32 = 12.0;
 const DEFAULT_WEBP_QUALITY: f32 = 90.0;
 const DEFAULT_RASTER_SCALE: f32 = 4.0;
 const DEFAULT_RASTER_MAX_PIXELS: u64 = 8_000_000;
@@ -15,21 +20,45 @@ const DEFAULT_PNG_QUANTIZE_DITHER: f32 = 1.0;
 const DEFAULT_TITLE_SIZE: f32 = 12.0;
 const DEFAULT_TITLE_OPACITY: f32 = 0.85;
 const DEFAULT_TITLE_MAX_WIDTH: usize = 80;
+const AUTO_COLOR: &str = "auto";
+const NO_COLOR: &str = "none";
+const DEFAULT_BRACKET_PALETTE: [&str; 3] = ["#FFD700", "#DA70D6", "#179FFF"];
+const DEFAULT_BACKGROUND_COLOR: &str = "#171717";
+const DEFAULT_TITLE_COLOR: &str = "#C5C8C6";
+const DEFAULT_LINE_NUMBER_COLOR: &str = "#777777";
+const DEFAULT_REDACT_ENTROPY_THRESHOLD: f32 = 4.0;
+const DEFAULT_REDACT_ENTROPY_MIN_LENGTH: usize = 20;
+const WHITESPACE_SPACE_MARKER: char = '\u{00B7}';
+const WHITESPACE_TAB_MARKER: char = '\u{2192}';
+const WHITESPACE_EOL_MARKER: char = '\u{00AC}';
+const WRAP_CONTINUATION_MARKER: char = '\u{21AA}';
 
 mod ansi;
+mod brackets;
+mod compose;
 mod config;
+mod document;
 mod fonts;
+mod git;
 mod input;
 mod layout;
 mod png;
+mod redact;
 mod render;
 mod svg;
+mod symbol;
 mod syntax;
 mod text;
 mod types;
+pub use compose::{render_panes, Pane};
 pub use config::{
-    Border, CjkRegion, Config, Font, FontSystemFallback, PngOptions, PngQuantPreset, PngStrip,
-    RasterBackend, RasterOptions, Shadow, TitleAlign, TitleOptions, TitlePathStyle,
+    Backdrop, BackdropFit, BackdropStyle, BlockOptions, Border, BracketOptions, CaptionOptions,
+    ChromeOptions, ChromeStyle, CjkRegion, Config, DedentOptions, DocumentFormat, Font, FontFiles,
+    FontSystemFallback, FooterOptions, GutterAlign, GutterOptions, LayoutChrome, LayoutDirection,
+    LayoutOptions, LineBound, LineRange, LineSelection, PaneSpec, PngOptions, PngQuantPreset,
+    PngStrip, RasterBackend, RasterOptions, RedactOptions, RedactStyle, RunOptions, Shadow,
+    TitleAlign, TitleOptions, TitlePathStyle, WatermarkOptions, WatermarkPosition,
+    WhitespaceOptions,
 };
 pub use render::{
     render, render_png, render_png_from_svg, render_png_webp_from_svg_once, render_svg,
@@ -37,6 +66,15 @@ pub use render::{
 };
 pub use types::{Error, InputSource, OutputFormat, RenderRequest, RenderResult, Result};
 
+/// Marks cells produced by expanding a tab character.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum TabCell {
+    #[default]
+    None,
+    Start,
+    Fill,
+}
+
 #[derive(Debug, Clone, Default, PartialEq)]
 struct TextStyle {
     fg: Option<String>,
@@ -45,6 +83,8 @@ struct TextStyle {
     italic: bool,
     underline: bool,
     strike: bool,
+    redacted: bool,
+    tab: TabCell,
 }
 
 #[derive(Debug, Clone, Default)]
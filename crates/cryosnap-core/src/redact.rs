@@ -0,0 +1,183 @@
+use crate::text::{cell_width, mark_cells};
+use crate::{Error, Line, RedactOptions, Result, TextStyle};
+use regex::Regex;
+
+/// Common API key and token formats.
+const BUILTIN_PATTERNS: &[&str] = &[
+    // AWS access key id
+    r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+    // GitHub tokens
+    r"\bgh[pousr]_[A-Za-z0-9]{36,}\b",
+    r"\bgithub_pat_[A-Za-z0-9_]{22,}\b",
+    // Slack tokens
+    r"\bxox[abposr]-[A-Za-z0-9-]{10,}",
+    // Stripe keys
+    r"\b[rs]k_(?:live|test)_[A-Za-z0-9]{16,}\b",
+    // Google API key
+    r"\bAIza[0-9A-Za-z_-]{35}",
+    // OpenAI / Anthropic style keys
+    r"\bsk-[A-Za-z0-9_-]{20,}",
+    // JSON Web Tokens
+    r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+",
+    // Email addresses
+    r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+];
+
+pub(crate) struct Redactor {
+    patterns: Vec<Regex>,
+    entropy: Option<(f32, usize)>,
+}
+
+impl Redactor {
+    pub(crate) fn new(options: &RedactOptions) -> Result<Option<Self>> {
+        if !options.is_active() {
+            return Ok(None);
+        }
+        let mut sources = options
+            .patterns
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if options.builtin {
+            sources.extend(BUILTIN_PATTERNS);
+        }
+        let patterns = sources
+            .into_iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|err| {
+                    Error::InvalidInput(format!("invalid redact pattern {pattern}: {err}"))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let entropy = options
+            .entropy
+            .then_some((options.entropy_threshold, options.entropy_min_length.max(1)));
+        Ok(Some(Self { patterns, entropy }))
+    }
+
+    /// Returns sorted, non-overlapping byte ranges to redact.
+    pub(crate) fn find(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        for pattern in &self.patterns {
+            ranges.extend(
+                pattern
+                    .find_iter(text)
+                    .filter(|m| !m.is_empty())
+                    .map(|m| (m.start(), m.end())),
+            );
+        }
+        if let Some((threshold, min_length)) = self.entropy {
+            ranges.extend(high_entropy_tokens(text, threshold, min_length));
+        }
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+}
+
+fn is_token_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '+' | '/' | '=' | '_' | '-' | '.')
+}
+
+pub(crate) fn shannon_entropy(text: &str) -> f32 {
+    let mut counts = [0usize; 128];
+    let mut total = 0usize;
+    for byte in text.bytes().filter(u8::is_ascii) {
+        counts[byte as usize] += 1;
+        total += 1;
+    }
+    if total == 0 {
+        return 0.0;
+    }
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f32 / total as f32;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn high_entropy_tokens(text: &str, threshold: f32, min_length: usize) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (idx, ch) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (is_token_char(ch), start) {
+            (true, None) => start = Some(idx),
+            (false, Some(begin)) => {
+                let token = &text[begin..idx];
+                let mixed = token.chars().any(|c| c.is_ascii_digit())
+                    && token.chars().any(|c| c.is_ascii_alphabetic());
+                if token.len() >= min_length && mixed && shannon_entropy(token) >= threshold {
+                    out.push((begin, idx));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn cell_ranges(text: &str, byte_ranges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    byte_ranges
+        .iter()
+        .map(|&(start, end)| {
+            let before = cell_width(&text[..start]);
+            (before, before + cell_width(&text[start..end]))
+        })
+        .filter(|(start, end)| start < end)
+        .collect()
+}
+
+/// Masks matches in each logical line. Redacted text is replaced by spaces
+/// of the same cell width and flagged so the renderer draws a block instead.
+pub(crate) fn redact_lines(lines: Vec<Line>, redactor: &Redactor) -> Vec<Line> {
+    lines
+        .into_iter()
+        .map(|line| {
+            let text = line
+                .spans
+                .iter()
+                .map(|span| span.text.as_str())
+                .collect::<String>();
+            let ranges = cell_ranges(&text, &redactor.find(&text));
+            mark_cells(line, &ranges, true, mark_redacted)
+        })
+        .collect()
+}
+
+/// Blanks secrets in plain text and returns the redacted cell ranges of
+/// every line, so they can be re-applied after wrapping and highlighting.
+pub(crate) fn redact_text(text: &str, redactor: &Redactor) -> (String, Vec<Vec<(usize, usize)>>) {
+    let mut out = Vec::new();
+    let mut all_ranges = Vec::new();
+    for line in text.split('\n') {
+        let found = redactor.find(line);
+        let mut redacted = String::with_capacity(line.len());
+        let mut cursor = 0;
+        for &(start, end) in &found {
+            redacted.push_str(&line[cursor..start]);
+            redacted.push_str(&" ".repeat(cell_width(&line[start..end])));
+            cursor = end;
+        }
+        redacted.push_str(&line[cursor..]);
+        all_ranges.push(cell_ranges(line, &found));
+        out.push(redacted);
+    }
+    (out.join("\n"), all_ranges)
+}
+
+pub(crate) fn mark_redacted(style: &mut TextStyle) {
+    style.redacted = true;
+}
@@ -1,25 +1,38 @@
 use crate::ansi::{parse_ansi, wrap_ansi_lines};
+use crate::brackets::{colorize_brackets, masked_chars, parse_bracket_position};
+use crate::compose::compose_run;
+use crate::document::select_code_block;
 use crate::fonts::{
     build_font_families, build_font_plan, build_fontdb, collect_font_fallback_needs,
-    ensure_fonts_available, load_app_font_families, needs_system_fonts, resolve_script_font_plan,
-    scan_text_fallbacks, FontFallbackNeeds, FontPlan, ScriptFontPlan,
+    ensure_fonts_available, load_app_font_families, needs_system_fonts, resolve_font_metrics,
+    resolve_script_font_plan, scan_text_fallbacks, FontFallbackNeeds, ScriptFontPlan,
 };
-use crate::input::{is_ansi_input, load_input};
-use crate::layout::scale_dimension;
+use crate::git::{blame_annotations, blame_input, BlameLine};
+use crate::input::{is_ansi_input, load_input, LoadedInput};
+use crate::layout::{leading_indent_cells, scale_dimension};
 use crate::png::{optimize_png, pixmap_to_webp, quantize_pixmap_to_png, quantize_png_bytes};
-use crate::svg::{build_svg, svg_font_face_css};
-use crate::syntax::highlight_code;
-use crate::text::{cut_text, detab, wrap_text};
+use crate::redact::{redact_lines, redact_text, Redactor};
+use crate::svg::{
+    build_svg, svg_fallback_font_css, svg_font_face_css, svg_text_chars, GutterLine, SvgRows,
+};
+use crate::symbol::select_symbol;
+use crate::syntax::{default_tab_width, highlight_code};
+use crate::text::{
+    common_indent, detab, mark_tab_rows, reindent_cells, reindent_lines, reindent_text,
+    select_lines, tab_cells, WrapRow,
+};
 use crate::{
-    Config, Error, FontSystemFallback, InputSource, OutputFormat, RasterBackend, RenderRequest,
-    RenderResult, Result, TitlePathStyle, DEFAULT_TAB_WIDTH,
+    BackdropStyle, Config, Error, FontSystemFallback, GutterOptions, InputSource, Line,
+    OutputFormat, RasterBackend, RenderRequest, RenderResult, Result, Span, TextStyle,
+    TitlePathStyle, ANSI_TAB_WIDTH, DEFAULT_TAB_WIDTH,
 };
+use base64::Engine;
 use once_cell::sync::Lazy;
 use std::env;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub fn render(request: &RenderRequest) -> Result<RenderResult> {
     let bytes = match request.format {
@@ -47,45 +60,262 @@ pub fn render_svg_planned(input: &InputSource, config: &Config) -> Result<Planne
     let rendered = render_svg_with_plan(input, config)?;
     Ok(PlannedSvg {
         bytes: rendered.bytes,
-        needs_system_fonts: rendered.font_plan.needs_system_fonts,
+        needs_system_fonts: rendered.needs_system_fonts,
     })
 }
 
 struct RenderedSvg {
     bytes: Vec<u8>,
-    font_plan: FontPlan,
+    needs_system_fonts: bool,
+    content_origin: (f32, f32),
 }
 
 fn render_svg_with_plan(input: &InputSource, config: &Config) -> Result<RenderedSvg> {
+    if let Some(command) = config.run.command.as_deref() {
+        let bytes = compose_run(input, command, config)?;
+        let needs_system_fonts = font_needs_from_svg(&bytes, config).needs_system_fonts;
+        return Ok(RenderedSvg {
+            bytes,
+            needs_system_fonts,
+            content_origin: (0.0, 0.0),
+        });
+    }
+    let source = load_source(input, config)?;
+    render_loaded(input, &source, config, "")
+}
+
+/// An input read (or run) once, with its blame when the gutter shows it,
+/// so it can be rendered again at another size.
+pub(crate) struct LoadedSource {
+    loaded: LoadedInput,
+    blame: Option<Vec<BlameLine>>,
+}
+
+pub(crate) fn load_source(input: &InputSource, config: &Config) -> Result<LoadedSource> {
     let loaded = load_input(input, Duration::from_millis(config.execute_timeout_ms))?;
+    let blame = if config.gutter.blame {
+        Some(blame_input(input, &loaded.text)?)
+    } else {
+        None
+    };
+    Ok(LoadedSource { loaded, blame })
+}
+
+/// Renders an already loaded `input`; `input` itself only names the title.
+/// Every element id starts with `id_prefix`.
+pub(crate) fn render_loaded_svg(
+    input: &InputSource,
+    source: &LoadedSource,
+    config: &Config,
+    id_prefix: &str,
+) -> Result<Vec<u8>> {
+    Ok(render_loaded(input, source, config, id_prefix)?.bytes)
+}
+
+/// Renders the window `config` describes with no rows in it, titled after
+/// `input` without reading it. Returns the SVG and the top-left corner of
+/// its content area.
+pub(crate) fn render_frame_svg(
+    input: &InputSource,
+    config: &Config,
+) -> Result<(Vec<u8>, (f32, f32))> {
+    let inlined;
+    let config = match inline_images(config)? {
+        Some(resolved) => {
+            inlined = resolved;
+            &inlined
+        }
+        None => config,
+    };
+    let mut title_text = resolve_title_text(input, config);
+    if let Some(redactor) = Redactor::new(&config.redact)? {
+        title_text = title_text.map(|title| redact_text(&title, &redactor).0);
+    }
+    let rows = SvgRows {
+        lines: &[],
+        gutter: &[],
+        tab_width: DEFAULT_TAB_WIDTH,
+        id_prefix: "",
+    };
+    // With no rows there is no text to take the default color.
+    let rendered = draw_svg(rows, config, "", title_text.as_deref())?;
+    Ok((rendered.bytes, rendered.content_origin))
+}
+
+fn render_loaded(
+    input: &InputSource,
+    source: &LoadedSource,
+    config: &Config,
+    id_prefix: &str,
+) -> Result<RenderedSvg> {
+    let inlined;
+    let config = match inline_images(config)? {
+        Some(resolved) => {
+            inlined = resolved;
+            &inlined
+        }
+        None => config,
+    };
+    let blame = source.blame.as_deref();
+    let loaded = select_code_block(source.loaded.clone(), &config.block)?;
     let is_ansi = is_ansi_input(&loaded, config);
-    let line_window = &config.lines;
 
-    let (lines, default_fg, line_offset) = if is_ansi {
-        let cut = cut_text(&loaded.text, line_window);
-        let mut lines = parse_ansi(&cut.text);
-        if config.wrap > 0 {
-            lines = wrap_ansi_lines(&lines, config.wrap);
+    let language = config.language.as_deref().or(loaded.language.as_deref());
+    // An extracted block keeps the document's path, but not its syntax:
+    // only the fence label names the language.
+    let syntax_path = loaded.path.as_deref().filter(|_| !config.block.is_active());
+    let redactor = Redactor::new(&config.redact)?;
+    let tab_width = match config.tab_width {
+        Some(0) => {
+            return Err(Error::InvalidInput(
+                "tab_width must be greater than 0".to_string(),
+            ))
+        }
+        Some(width) => width,
+        None if is_ansi => ANSI_TAB_WIDTH,
+        None => default_tab_width(&loaded.text, syntax_path, language),
+    };
+
+    let (segments, default_fg) = if is_ansi {
+        if config.symbol.is_some() {
+            return Err(Error::InvalidInput(
+                "symbol selection is not supported for ANSI input".to_string(),
+            ));
         }
-        (lines, "#C5C8C6".to_string(), cut.start)
+        let mut cuts = select_lines(&loaded.text, &config.lines)?
+            .into_iter()
+            .map(|cut| (cut.start, parse_ansi(&cut.text, tab_width)))
+            .collect::<Vec<_>>();
+        if config.dedent.enabled {
+            let remove = cuts
+                .iter()
+                .flat_map(|(_, lines)| lines.iter())
+                .filter_map(|line| leading_indent_cells(line, tab_width))
+                .min()
+                .unwrap_or(0);
+            for (_, lines) in &mut cuts {
+                *lines = reindent_lines(std::mem::take(lines), remove, config.dedent.indent);
+            }
+        }
+        let segments = cuts
+            .into_iter()
+            .map(|(start, mut lines)| {
+                if let Some(redactor) = &redactor {
+                    lines = redact_lines(lines, redactor);
+                }
+                let (lines, layout) = wrap_ansi_lines(&lines, config.wrap);
+                (loaded.line_offset + start, lines, layout)
+            })
+            .collect::<Vec<_>>();
+        (segments, "#C5C8C6".to_string())
     } else {
-        let mut text = detab(&loaded.text, DEFAULT_TAB_WIDTH);
-        let cut = cut_text(&text, line_window);
-        text = cut.text;
-        if config.wrap > 0 {
-            text = wrap_text(&text, config.wrap);
+        let tabs = if config.whitespace.visible {
+            tab_cells(&loaded.text, tab_width)
+        } else {
+            Vec::new()
+        };
+        let mut text = detab(&loaded.text, tab_width);
+        let mut symbol_start = 0;
+        if let Some(symbol) = config.symbol.as_deref().filter(|v| !v.trim().is_empty()) {
+            let selected = select_symbol(&text, syntax_path, language, symbol)?;
+            symbol_start = selected.start;
+            text = selected.text;
+        }
+        let cuts = select_lines(&text, &config.lines)?;
+        let remove = if config.dedent.enabled {
+            cuts.iter()
+                .filter_map(|cut| common_indent(&cut.text))
+                .min()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        let bracket_focus = config
+            .brackets
+            .match_at
+            .as_deref()
+            .map(parse_bracket_position)
+            .transpose()?;
+        let (mut highlighted, default_fg) =
+            highlight_code(&text, syntax_path, language, &config.theme)?;
+        let line_base = loaded.line_offset + symbol_start;
+        if config.brackets.rainbow || bracket_focus.is_some() {
+            let masks = masked_chars(&text, syntax_path, language)?;
+            highlighted = colorize_brackets(
+                highlighted,
+                &masks,
+                &[],
+                line_base,
+                &config.brackets,
+                bracket_focus,
+            );
+        }
+        let mut segments = Vec::new();
+        for cut in cuts {
+            let count = cut.text.split('\n').count();
+            // A window past the end cuts nothing and draws one empty row.
+            let mut lines = highlighted
+                .get(cut.start..cut.start + count)
+                .map_or_else(|| vec![Line::default()], <[Line]>::to_vec);
+            let mut cut_tabs = Vec::new();
+            if config.whitespace.visible {
+                let first = (symbol_start + cut.start).min(tabs.len());
+                let last = (first + count).min(tabs.len());
+                cut_tabs = tabs[first..last].to_vec();
+            }
+            let mut cut_text = cut.text;
+            if config.dedent.enabled {
+                lines = reindent_lines(lines, remove, config.dedent.indent);
+                cut_tabs = reindent_cells(&cut_text, &cut_tabs, remove, config.dedent.indent);
+                cut_text = reindent_text(&cut_text, remove, config.dedent.indent);
+            }
+            if config.whitespace.visible {
+                lines = mark_tab_rows(lines, &cut_text, &cut_tabs, 0);
+            }
+            if let Some(redactor) = &redactor {
+                lines = redact_lines(lines, redactor);
+            }
+            let (lines, layout) = wrap_ansi_lines(&lines, config.wrap);
+            segments.push((line_base + cut.start, lines, layout));
+        }
+        (segments, default_fg)
+    };
+    let (lines, line_numbers, continuations) = join_line_segments(segments, &config.elision);
+    let mut gutter = build_gutter(&line_numbers, &continuations, blame, &config.gutter);
+
+    let mut title_text = resolve_title_text(input, config);
+    if let Some(redactor) = &redactor {
+        title_text = title_text.map(|title| redact_text(&title, redactor).0);
+        for line in &mut gutter {
+            if let Some(blame) = line.blame.as_mut() {
+                *blame = redact_text(blame, redactor).0;
+            }
         }
-        let (lines, default_fg) = highlight_code(
-            &text,
-            loaded.path.as_deref(),
-            config.language.as_deref(),
-            &config.theme,
-        )?;
-        (lines, default_fg, cut.start)
+    }
+    let rows = SvgRows {
+        lines: &lines,
+        gutter: &gutter,
+        tab_width,
+        id_prefix,
     };
+    draw_svg(rows, config, &default_fg, title_text.as_deref())
+}
 
-    let title_text = resolve_title_text(input, config);
-    let needs = collect_font_fallback_needs(&lines, title_text.as_deref());
+/// Plans and embeds the fonts for `rows` and the title, then builds the SVG,
+/// outlining its text when `text_to_path` is set.
+fn draw_svg(
+    rows: SvgRows,
+    config: &Config,
+    default_fg: &str,
+    title_text: Option<&str>,
+) -> Result<RenderedSvg> {
+    if config.text_to_path && !config.font.features.is_empty() {
+        return Err(Error::InvalidInput(
+            "font.features only apply to SVG text, not to text_to_path outlines".to_string(),
+        ));
+    }
+    let SvgRows { lines, gutter, .. } = rows;
+    let needs = collect_font_fallback_needs(lines, title_text);
     let script_plan = resolve_script_font_plan(config, &needs);
     let script_plan = match script_plan {
         Ok(plan) => plan,
@@ -96,39 +326,188 @@ fn render_svg_with_plan(input: &InputSource, config: &Config) -> Result<Rendered
     };
     let _ = ensure_fonts_available(config, &needs, &script_plan);
     let app_families = load_app_font_families(config).unwrap_or_default();
-    let font_plan = build_font_plan(config, &needs, &app_families, &script_plan.families);
-    let font_css = svg_font_face_css(config)?;
-    let svg = build_svg(
-        &lines,
-        config,
-        &default_fg,
-        font_css,
-        line_offset,
-        title_text.as_deref(),
-        &font_plan.font_family,
-    );
+    let mut font_plan = build_font_plan(config, &needs, &app_families, &script_plan.families);
+    font_plan.metrics = resolve_font_metrics(config, font_plan.needs_system_fonts);
+    let chars = svg_text_chars(lines, gutter, title_text, config);
+    let mut font_css = svg_font_face_css(config, &chars)?;
+    if config.font.embed_fallbacks && !config.text_to_path {
+        let fontdb = build_fontdb(config, font_plan.needs_system_fonts)?;
+        if let Some(rules) = svg_fallback_font_css(lines, &chars, &font_plan, config, &fontdb) {
+            font_css = Some(font_css.unwrap_or_default() + &rules);
+        }
+    }
+    let svg = build_svg(rows, config, default_fg, font_css, title_text, &font_plan);
+    let bytes = if config.text_to_path {
+        outline_svg_text(
+            svg.markup.as_bytes(),
+            config,
+            font_plan.needs_system_fonts,
+            rows.id_prefix,
+        )?
+    } else {
+        svg.markup.into_bytes()
+    };
     Ok(RenderedSvg {
-        bytes: svg.into_bytes(),
-        font_plan,
+        bytes,
+        needs_system_fonts: font_plan.needs_system_fonts,
+        content_origin: svg.content_origin,
     })
 }
 
+/// Replaces backdrop and watermark image paths with data URLs so the SVG
+/// carries the images themselves.
+fn inline_images(config: &Config) -> Result<Option<Config>> {
+    let backdrop = match config.backdrop.image.as_deref() {
+        _ if config.backdrop.style != BackdropStyle::Image => None,
+        None => {
+            return Err(Error::InvalidInput(
+                "backdrop.image is required for the image backdrop".to_string(),
+            ))
+        }
+        Some(path) => image_data_url(path, "backdrop")?,
+    };
+    let logo = match config.watermark.image.as_deref() {
+        Some(path) => image_data_url(path, "watermark")?,
+        None => None,
+    };
+    if backdrop.is_none() && logo.is_none() {
+        return Ok(None);
+    }
+    let mut resolved = config.clone();
+    if backdrop.is_some() {
+        resolved.backdrop.image = backdrop;
+    }
+    if logo.is_some() {
+        resolved.watermark.image = logo;
+    }
+    Ok(Some(resolved))
+}
+
+/// Reads a PNG or JPEG into a data URL; `None` when `path` already is one.
+fn image_data_url(path: &str, what: &str) -> Result<Option<String>> {
+    if path.starts_with("data:") {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(path)?;
+    let mime = if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else {
+        return Err(Error::InvalidInput(format!(
+            "{what} image must be PNG or JPEG: {path}"
+        )));
+    };
+    Ok(Some(format!(
+        "data:{mime};base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )))
+}
+
+/// Re-writes the SVG through usvg with every text node flattened to glyph
+/// outlines from the planned fonts, so it renders the same without them.
+/// The ids usvg writes start with `id_prefix`.
+fn outline_svg_text(
+    svg: &[u8],
+    config: &Config,
+    needs_system_fonts: bool,
+    id_prefix: &str,
+) -> Result<Vec<u8>> {
+    let mut opt = usvg::Options::default();
+    *opt.fontdb_mut() = build_fontdb(config, needs_system_fonts)?;
+    let tree = usvg::Tree::from_data(svg, &opt)
+        .map_err(|err| Error::Render(format!("usvg parse: {err}")))?;
+    let write = usvg::WriteOptions {
+        id_prefix: Some(id_prefix.to_string()).filter(|prefix| !prefix.is_empty()),
+        ..usvg::WriteOptions::default()
+    };
+    Ok(tree.to_string(&write).into_bytes())
+}
+
+/// Joins selected line ranges, inserting an elision line between gaps.
+/// Returns the rows with the 1-based source line each one belongs to and
+/// whether it continues a wrapped line.
+fn join_line_segments(
+    segments: Vec<(usize, Vec<Line>, Vec<WrapRow>)>,
+    elision: &str,
+) -> (Vec<Line>, Vec<Option<usize>>, Vec<bool>) {
+    let mut lines = Vec::new();
+    let mut numbers = Vec::new();
+    let mut continuations = Vec::new();
+    for (idx, (start, segment, layout)) in segments.into_iter().enumerate() {
+        if idx > 0 {
+            lines.push(Line {
+                spans: vec![Span {
+                    text: elision.to_string(),
+                    style: TextStyle::default(),
+                }],
+            });
+            numbers.push(None);
+            continuations.push(false);
+        }
+        for row in 0..segment.len() {
+            let layout = layout.get(row);
+            numbers.push(Some(start + layout.map_or(row, |layout| layout.line) + 1));
+            continuations.push(layout.is_some_and(|layout| layout.continuation));
+        }
+        lines.extend(segment);
+    }
+    (lines, numbers, continuations)
+}
+
+/// Continuation rows share the blame of their source line but show a
+/// continuation marker instead of a number. Displayed numbers start at
+/// `options.start` when set; in relative mode every line but the focus line
+/// shows its distance from it.
+pub(crate) fn build_gutter(
+    numbers: &[Option<usize>],
+    continuations: &[bool],
+    blame: Option<&[BlameLine]>,
+    options: &GutterOptions,
+) -> Vec<GutterLine> {
+    let first = numbers.iter().flatten().copied().next().unwrap_or(1);
+    let focus = options.focus.or(options.relative.then_some(first));
+    let display = |number: usize| {
+        let absolute = match options.start {
+            Some(start) => (number + start).saturating_sub(first),
+            None => number,
+        };
+        match focus.filter(|focus| options.relative && *focus != number) {
+            Some(focus) => number.abs_diff(focus),
+            None => absolute,
+        }
+    };
+    let annotations = match blame {
+        Some(blame) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            blame_annotations(blame, numbers, now)
+        }
+        None => vec![None; numbers.len()],
+    };
+    numbers
+        .iter()
+        .zip(continuations)
+        .zip(annotations)
+        .map(|((number, continuation), blame)| GutterLine {
+            number: number.filter(|_| !continuation).map(display),
+            blame,
+            continuation: *continuation,
+            focus: number.is_some() && *number == focus,
+        })
+        .collect()
+}
+
 pub fn render_png(input: &InputSource, config: &Config) -> Result<Vec<u8>> {
     let rendered = render_svg_with_plan(input, config)?;
-    render_png_from_svg_with_plan(
-        &rendered.bytes,
-        config,
-        rendered.font_plan.needs_system_fonts,
-    )
+    render_png_from_svg_with_plan(&rendered.bytes, config, rendered.needs_system_fonts)
 }
 
 pub fn render_webp(input: &InputSource, config: &Config) -> Result<Vec<u8>> {
     let rendered = render_svg_with_plan(input, config)?;
-    render_webp_from_svg_with_plan(
-        &rendered.bytes,
-        config,
-        rendered.font_plan.needs_system_fonts,
-    )
+    render_webp_from_svg_with_plan(&rendered.bytes, config, rendered.needs_system_fonts)
 }
 
 pub fn render_png_from_svg(svg: &[u8], config: &Config) -> Result<Vec<u8>> {
@@ -333,6 +712,9 @@ fn rasterize_svg(
     config: &Config,
     needs_system_fonts: bool,
 ) -> Result<tiny_skia::Pixmap> {
+    if !config.font.features.is_empty() {
+        eprintln!("cryosnap: font.features are ignored by the resvg backend");
+    }
     let mut opt = usvg::Options::default();
     let fontdb = build_fontdb(config, needs_system_fonts)?;
     *opt.fontdb_mut() = fontdb;
@@ -394,6 +776,13 @@ pub(crate) fn resolve_title_text(input: &InputSource, config: &Config) -> Option
     }
     let auto = match input {
         InputSource::File(path) => title_text_from_path(path, config.title.path_style),
+        InputSource::Git { path, rev } => {
+            format!(
+                "{}@{}",
+                title_text_from_path(path, config.title.path_style),
+                rev
+            )
+        }
         InputSource::Command(cmd) => format!("cmd: {}", cmd),
         InputSource::Text(_) => return None,
     };
@@ -1,17 +1,27 @@
 use base64::Engine;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use unicode_script::{Script, UnicodeScript};
+use unicode_width::UnicodeWidthStr;
 
 use crate::fonts::{
-    cjk_region_families, is_cjk, is_emoji, is_private_use, locale_cjk_region, push_family,
-    AUTO_FALLBACK_EMOJI, AUTO_FALLBACK_GLOBAL, AUTO_FALLBACK_NF,
+    cjk_region_families, embed_face, encode_woff2, fallback_faces, is_cjk, is_emoji,
+    is_private_use, locale_cjk_region, push_family, FontPlan, AUTO_FALLBACK_EMOJI,
+    AUTO_FALLBACK_GLOBAL, AUTO_FALLBACK_NF,
 };
-use crate::layout::{expand_box, line_width_cells, span_width_px, truncate_to_cells};
-use crate::render::sanitize_title_text;
+use crate::layout::{
+    advance_cells, cell_clusters, expand_box, graphemes, leading_indent_cells, line_width_cells,
+    span_width_px, text_width_cells, truncate_to_cells,
+};
+use crate::render::{sanitize_title_text, title_text_from_path};
+use crate::syntax::theme_colors;
 use crate::{
-    CjkRegion, Config, Line, Result, TitleAlign, FONT_HEIGHT_TO_WIDTH_RATIO,
+    Backdrop, BackdropFit, BackdropStyle, ChromeStyle, CjkRegion, Config, Error, Font, GutterAlign,
+    Line, RedactStyle, Result, TabCell, TitleAlign, WatermarkPosition, ANNOTATION_GAP, AUTO_COLOR,
+    NO_COLOR, WHITESPACE_EOL_MARKER, WHITESPACE_SPACE_MARKER, WHITESPACE_TAB_MARKER,
     WINDOW_CONTROLS_HEIGHT, WINDOW_CONTROLS_SPACING, WINDOW_CONTROLS_X_OFFSET,
+    WINDOW_TAB_BAR_HEIGHT, WINDOW_TAB_PADDING, WRAP_CONTINUATION_MARKER,
 };
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -98,7 +108,7 @@ impl FontFamilyVariants {
     }
 }
 
-fn parse_font_family_list(value: &str) -> Vec<String> {
+pub(crate) fn parse_font_family_list(value: &str) -> Vec<String> {
     value
         .split(',')
         .map(|part| part.trim())
@@ -119,15 +129,8 @@ fn build_font_family_variant(base: &[String], prefix: &[&str]) -> String {
     out.join(", ")
 }
 
-pub(crate) fn build_svg(
-    lines: &[Line],
-    config: &Config,
-    default_fg: &str,
-    font_css: Option<String>,
-    line_offset: usize,
-    title_text: Option<&str>,
-    font_family: &str,
-) -> String {
+fn font_family_variants(font: &FontPlan, config: &Config) -> FontFamilyVariants {
+    let font_family = font.font_family.as_str();
     let base_families = parse_font_family_list(font_family);
     let default_family = if base_families.is_empty() {
         font_family.to_string()
@@ -138,52 +141,587 @@ pub(crate) fn build_svg(
         CjkRegion::Auto => locale_cjk_region().unwrap_or(CjkRegion::Sc),
         other => other,
     };
-    let font_variants = FontFamilyVariants {
+    FontFamilyVariants {
         default: default_family,
         cjk: build_font_family_variant(&base_families, cjk_region_families(cjk_region)),
         emoji: build_font_family_variant(&base_families, AUTO_FALLBACK_EMOJI),
         nerd: build_font_family_variant(&base_families, AUTO_FALLBACK_NF),
         unicode: build_font_family_variant(&base_families, AUTO_FALLBACK_GLOBAL),
+    }
+}
+
+struct ChromeColors {
+    background: String,
+    title: String,
+    line_number: String,
+    focus_number: String,
+    gutter_background: Option<String>,
+    whitespace: String,
+}
+
+fn resolve_color(value: &str, auto: &str) -> String {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || trimmed.eq_ignore_ascii_case(AUTO_COLOR) {
+        auto.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn resolve_chrome_colors(config: &Config) -> ChromeColors {
+    let theme = theme_colors(&config.theme);
+    ChromeColors {
+        background: resolve_color(&config.background, &theme.background),
+        title: resolve_color(&config.title.color, &theme.title),
+        line_number: resolve_color(&config.gutter.color, &theme.gutter),
+        focus_number: resolve_color(&config.gutter.focus_color, &theme.foreground),
+        gutter_background: Some(config.gutter.background.trim())
+            .filter(|value| !value.is_empty() && !value.eq_ignore_ascii_case(NO_COLOR))
+            .map(|value| resolve_color(value, &theme.gutter_background)),
+        whitespace: resolve_color(&config.whitespace.color, &theme.guide),
+    }
+}
+
+/// Height the title bar adds above the padding.
+fn title_bar_height(config: &Config) -> f32 {
+    match (config.window_controls, config.chrome.tabs) {
+        (false, _) => 0.0,
+        (true, false) => WINDOW_CONTROLS_HEIGHT,
+        (true, true) => WINDOW_TAB_BAR_HEIGHT,
+    }
+}
+
+/// Draws the window buttons for `style` centered on `y` between the inner
+/// window edges `left` and `right`. Returns the markup and the widths the
+/// buttons take up at the left and right edge.
+fn window_controls_svg(
+    style: ChromeStyle,
+    colors: &ChromeColors,
+    left: f32,
+    right: f32,
+    y: f32,
+) -> (String, f32, f32) {
+    let mut svg = String::new();
+    let circle = |svg: &mut String, cx: f32, r: f32, fill: &str, opacity: &str| {
+        svg.push_str(&format!(
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="{}"{}/>"#,
+            cx,
+            y,
+            r,
+            escape_attr(fill),
+            opacity
+        ));
+    };
+    // Minimize, maximize and close glyphs, `half` px from their center.
+    let glyphs = |svg: &mut String, centers: [f32; 3], half: f32, stroke: f32| {
+        let [min, max, close] = centers;
+        svg.push_str(&format!(
+            r#"<path d="M {:.2} {:.2} H {:.2} M {:.2} {:.2} h {:.2} v {:.2} h {:.2} Z M {:.2} {:.2} L {:.2} {:.2} M {:.2} {:.2} L {:.2} {:.2}" fill="none" stroke="{}" stroke-width="{:.2}" stroke-linecap="round"/>"#,
+            min - half,
+            y,
+            min + half,
+            max - half,
+            y - half,
+            2.0 * half,
+            2.0 * half,
+            -2.0 * half,
+            close - half,
+            y - half,
+            close + half,
+            y + half,
+            close + half,
+            y - half,
+            close - half,
+            y + half,
+            escape_attr(&colors.title),
+            stroke
+        ));
+    };
+
+    match style {
+        ChromeStyle::Macos => {
+            let r = 5.5;
+            let x = left + WINDOW_CONTROLS_X_OFFSET;
+            for (idx, fill) in ["#FF5A54", "#E6BF29", "#52C12B"].into_iter().enumerate() {
+                circle(
+                    &mut svg,
+                    x + WINDOW_CONTROLS_SPACING * idx as f32,
+                    r,
+                    fill,
+                    "",
+                );
+            }
+            (
+                svg,
+                WINDOW_CONTROLS_X_OFFSET + WINDOW_CONTROLS_SPACING * 2.0 + r,
+                0.0,
+            )
+        }
+        ChromeStyle::Minimal => {
+            let (r, spacing) = (4.0, 14.0);
+            let x = left + WINDOW_CONTROLS_X_OFFSET;
+            for idx in 0..3 {
+                circle(
+                    &mut svg,
+                    x + spacing * idx as f32,
+                    r,
+                    &colors.line_number,
+                    "",
+                );
+            }
+            (svg, WINDOW_CONTROLS_X_OFFSET + spacing * 2.0 + r, 0.0)
+        }
+        ChromeStyle::Gnome => {
+            let (r, spacing) = (6.5, 20.0);
+            let close = right - WINDOW_CONTROLS_X_OFFSET - r;
+            let centers = [close - 2.0 * spacing, close - spacing, close];
+            for cx in centers {
+                circle(&mut svg, cx, r, &colors.title, r#" fill-opacity="0.12""#);
+            }
+            glyphs(&mut svg, centers, 2.5, 1.2);
+            (svg, 0.0, WINDOW_CONTROLS_X_OFFSET + 2.0 * spacing + 2.0 * r)
+        }
+        ChromeStyle::Windows => {
+            let (half, spacing) = (4.0, 28.0);
+            let close = right - WINDOW_CONTROLS_X_OFFSET - half;
+            glyphs(
+                &mut svg,
+                [close - 2.0 * spacing, close - spacing, close],
+                half,
+                1.0,
+            );
+            (
+                svg,
+                0.0,
+                WINDOW_CONTROLS_X_OFFSET + 2.0 * spacing + 2.0 * half,
+            )
+        }
+        ChromeStyle::None => (svg, 0.0, 0.0),
+    }
+}
+
+/// Backdrop behind the window, filling the whole image. The window is
+/// drawn on top, so it only shows through the margins. Its paint server
+/// id starts with `ids`.
+fn backdrop_svg(backdrop: &Backdrop, width: f32, height: f32, ids: &str) -> String {
+    let colors = &backdrop.colors;
+    let Some(first) = colors.first().map(|color| escape_attr(color)) else {
+        return String::new();
+    };
+    let ink = colors
+        .get(1)
+        .map(|color| escape_attr(color))
+        .unwrap_or_else(|| first.clone());
+    let size = backdrop.size.max(1.0);
+    let stops = || {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        colors
+            .iter()
+            .enumerate()
+            .map(|(idx, color)| {
+                format!(
+                    r#"<stop offset="{:.4}" stop-color="{}"/>"#,
+                    idx as f32 / last,
+                    escape_attr(color)
+                )
+            })
+            .collect::<String>()
+    };
+    let fill = |paint: &str| {
+        format!(
+            r#"<rect x="0" y="0" width="{:.2}" height="{:.2}" fill="{}"/>"#,
+            width, height, paint
+        )
+    };
+    let paint = format!("url(#{ids}backdrop)");
+
+    match backdrop.style {
+        BackdropStyle::None => String::new(),
+        BackdropStyle::Solid => fill(&first),
+        BackdropStyle::Linear => {
+            let (dx, dy) = {
+                let rad = backdrop.angle.to_radians();
+                (rad.sin() / 2.0, -rad.cos() / 2.0)
+            };
+            format!(
+                r#"<defs><linearGradient id="{}backdrop" x1="{:.4}" y1="{:.4}" x2="{:.4}" y2="{:.4}">{}</linearGradient></defs>{}"#,
+                ids,
+                0.5 - dx,
+                0.5 - dy,
+                0.5 + dx,
+                0.5 + dy,
+                stops(),
+                fill(&paint)
+            )
+        }
+        BackdropStyle::Radial => format!(
+            r#"<defs><radialGradient id="{}backdrop" cx="0.5" cy="0.5" r="0.75">{}</radialGradient></defs>{}"#,
+            ids,
+            stops(),
+            fill(&paint)
+        ),
+        BackdropStyle::Image => {
+            let Some(href) = backdrop.image.as_deref() else {
+                return String::new();
+            };
+            let aspect = match backdrop.fit {
+                BackdropFit::Cover => "xMidYMid slice",
+                BackdropFit::Contain => "xMidYMid meet",
+            };
+            format!(
+                r#"{}<image x="0" y="0" width="{:.2}" height="{:.2}" preserveAspectRatio="{}" href="{}"/>"#,
+                fill(&first),
+                width,
+                height,
+                aspect,
+                escape_attr(href)
+            )
+        }
+        BackdropStyle::Dots => format!(
+            r#"<defs><pattern id="{ids}backdrop" patternUnits="userSpaceOnUse" width="{size:.2}" height="{size:.2}"><rect width="{size:.2}" height="{size:.2}" fill="{first}"/><circle cx="{half:.2}" cy="{half:.2}" r="{dot:.2}" fill="{ink}"/></pattern></defs>{rect}"#,
+            half = size / 2.0,
+            dot = (size / 10.0).max(0.5),
+            rect = fill(&paint)
+        ),
+        BackdropStyle::Grid => format!(
+            r#"<defs><pattern id="{ids}backdrop" patternUnits="userSpaceOnUse" width="{size:.2}" height="{size:.2}"><rect width="{size:.2}" height="{size:.2}" fill="{first}"/><path d="M {size:.2} 0 L 0 0 0 {size:.2}" fill="none" stroke="{ink}" stroke-width="1"/></pattern></defs>{rect}"#,
+            rect = fill(&paint)
+        ),
+        BackdropStyle::Noise => format!(
+            r#"<defs><filter id="{}backdrop" x="0" y="0" width="1" height="1"><feTurbulence type="fractalNoise" baseFrequency="{:.4}" numOctaves="3" stitchTiles="stitch"/><feColorMatrix type="saturate" values="0"/><feComponentTransfer><feFuncA type="linear" slope="0.35"/></feComponentTransfer></filter></defs>{}<rect x="0" y="0" width="{:.2}" height="{:.2}" filter="{}"/>"#,
+            ids,
+            8.0 / size,
+            fill(&first),
+            width,
+            height,
+            paint
+        ),
+    }
+}
+
+fn title_size(config: &Config) -> f32 {
+    if config.title.size > 0.0 {
+        config.title.size
+    } else {
+        (config.font.size - 2.0).max(8.0)
+    }
+}
+
+/// One line of caption or footer text under the window.
+struct AnnotationRow {
+    text: String,
+    family: String,
+    size: f32,
+    color: String,
+    opacity: f32,
+    align: TitleAlign,
+}
+
+/// Caption lines followed by the footer line, top to bottom.
+fn annotation_rows(config: &Config, default_family: &str) -> Vec<AnnotationRow> {
+    let caption = &config.caption;
+    let footer = &config.footer;
+    let family =
+        |family: &Option<String>| family.clone().unwrap_or_else(|| default_family.to_string());
+    let mut rows = caption
+        .text
+        .iter()
+        .flat_map(|text| text.lines())
+        .map(|line| AnnotationRow {
+            text: sanitize_title_text(line),
+            family: family(&caption.family),
+            size: if caption.size > 0.0 {
+                caption.size
+            } else {
+                config.font.size
+            },
+            color: caption.color.clone(),
+            opacity: caption.opacity.clamp(0.0, 1.0),
+            align: caption.align,
+        })
+        .collect::<Vec<_>>();
+    if let Some(text) = footer_text(config) {
+        rows.push(AnnotationRow {
+            text,
+            family: family(&footer.family),
+            size: if footer.size > 0.0 {
+                footer.size
+            } else {
+                title_size(config)
+            },
+            color: footer.color.clone(),
+            opacity: footer.opacity.clamp(0.0, 1.0),
+            align: footer.align,
+        });
+    }
+    rows
+}
+
+/// The footer's author, URL and date joined by its separator.
+pub(crate) fn footer_text(config: &Config) -> Option<String> {
+    let footer = &config.footer;
+    let date = footer.date.as_deref().map(|date| {
+        if date.eq_ignore_ascii_case("today") {
+            today()
+        } else {
+            date.to_string()
+        }
+    });
+    let parts = [footer.author.clone(), footer.url.clone(), date]
+        .into_iter()
+        .flatten()
+        .map(|part| sanitize_title_text(&part))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>();
+    (!parts.is_empty()).then(|| parts.join(&footer.separator))
+}
+
+/// Current UTC date as YYYY-MM-DD.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    // Civil date from days since the epoch (Howard Hinnant's algorithm).
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Pixel size of an inlined PNG or JPEG logo, read from its header.
+fn data_url_image_size(href: &str) -> Option<(f32, f32)> {
+    let data = href.split_once(";base64,")?.1;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .ok()?;
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") && bytes.len() >= 24 {
+        let width = u32::from_be_bytes(bytes[16..20].try_into().ok()?);
+        let height = u32::from_be_bytes(bytes[20..24].try_into().ok()?);
+        return Some((width as f32, height as f32));
+    }
+    let mut pos = 2;
+    while pos + 9 < bytes.len() && bytes[pos] == 0xFF {
+        let marker = bytes[pos + 1];
+        let len = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        if matches!(marker, 0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF) {
+            let height = u16::from_be_bytes([bytes[pos + 5], bytes[pos + 6]]);
+            let width = u16::from_be_bytes([bytes[pos + 7], bytes[pos + 8]]);
+            return Some((width as f32, height as f32));
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+/// Watermark text and logo, laid out in a box of `width` x `height`.
+struct Watermark {
+    width: f32,
+    height: f32,
+    markup: String,
+}
+
+/// Builds the watermark with its top-left corner at the origin, or `None`
+/// when neither text nor image is set.
+fn watermark_svg(config: &Config, default_family: &str, advance: f32) -> Option<Watermark> {
+    let watermark = &config.watermark;
+    let text = watermark
+        .text
+        .as_deref()
+        .map(sanitize_title_text)
+        .filter(|text| !text.is_empty());
+    let image = watermark.image.as_deref().filter(|href| !href.is_empty());
+    if text.is_none() && image.is_none() {
+        return None;
+    }
+    let size = if watermark.size > 0.0 {
+        watermark.size
+    } else {
+        title_size(config)
     };
+    let mut markup = String::new();
+    let mut width = 0.0;
+    let height = if image.is_some() { size * 2.0 } else { size };
+    if let Some(href) = image {
+        let logo_width = data_url_image_size(href)
+            .filter(|(w, h)| *w > 0.0 && *h > 0.0)
+            .map(|(w, h)| height * w / h)
+            .unwrap_or(height);
+        markup.push_str(&format!(
+            r#"<image x="0.00" y="0.00" width="{:.2}" height="{:.2}" preserveAspectRatio="xMidYMid meet" href="{}"/>"#,
+            logo_width,
+            height,
+            escape_attr(href)
+        ));
+        width += logo_width;
+    }
+    if let Some(text) = text {
+        if width > 0.0 {
+            width += size / 2.0;
+        }
+        let family = watermark.family.as_deref().unwrap_or(default_family);
+        markup.push_str(&format!(
+            r#"<text x="{:.2}" y="{:.2}" fill="{}" font-family="{}" font-size="{:.2}px">{}</text>"#,
+            width,
+            height / 2.0 + size * 0.35,
+            escape_attr(&watermark.color),
+            escape_attr(family),
+            size,
+            escape_text(&text)
+        ));
+        width += text_width_cells(&text) as f32 * size * advance;
+    }
+    Some(Watermark {
+        width,
+        height,
+        markup,
+    })
+}
+
+/// Gutter content for one rendered row.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct GutterLine {
+    pub(crate) number: Option<usize>,
+    pub(crate) blame: Option<String>,
+    pub(crate) continuation: bool,
+    pub(crate) focus: bool,
+}
+
+/// The rows to draw with their gutter, the tab width they were expanded
+/// with, and the prefix for every element id so several SVGs can share a
+/// document.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SvgRows<'a> {
+    pub(crate) lines: &'a [Line],
+    pub(crate) gutter: &'a [GutterLine],
+    pub(crate) tab_width: usize,
+    pub(crate) id_prefix: &'a str,
+}
+
+/// Built SVG markup and the top-left corner of its content area.
+#[derive(Debug, Clone)]
+pub(crate) struct SvgDocument {
+    pub(crate) markup: String,
+    pub(crate) content_origin: (f32, f32),
+}
+
+pub(crate) fn build_svg(
+    rows: SvgRows,
+    config: &Config,
+    default_fg: &str,
+    font_css: Option<String>,
+    title_text: Option<&str>,
+    font: &FontPlan,
+) -> SvgDocument {
+    let SvgRows {
+        lines,
+        gutter,
+        tab_width,
+        id_prefix: ids,
+    } = rows;
+    let font_variants = font_family_variants(font, config);
 
+    let colors = resolve_chrome_colors(config);
     let padding = expand_box(&config.padding);
     let margin = expand_box(&config.margin);
     let mut pad_top = padding[0];
     let pad_right = padding[1];
     let pad_bottom = padding[2];
     let pad_left = padding[3];
-    let margin_top = margin[0];
-    let margin_right = margin[1];
-    let margin_bottom = margin[2];
-    let margin_left = margin[3];
+    let mut margin_top = margin[0];
+    let mut margin_right = margin[1];
+    let mut margin_bottom = margin[2];
+    let mut margin_left = margin[3];
 
-    if config.window_controls {
-        pad_top += WINDOW_CONTROLS_HEIGHT;
-    }
+    let bar_height = title_bar_height(config);
+    pad_top += bar_height;
 
     let line_height_px = config.font.size * config.line_height;
-    let char_width = config.font.size / FONT_HEIGHT_TO_WIDTH_RATIO;
+    let char_width = config.font.size * font.metrics.advance;
+    let baseline = font.metrics.baseline(config.font.size, line_height_px);
     let line_count = std::cmp::max(1, lines.len());
 
+    let separator_cells = config.gutter.separator.width();
     let line_number_cells = if config.show_line_numbers {
-        let digits = std::cmp::max(3, line_count.to_string().len());
-        digits + 2
+        let max_number = gutter.iter().filter_map(|row| row.number).max();
+        let digits = std::cmp::max(
+            config.gutter.min_digits,
+            max_number.unwrap_or(line_count).to_string().len(),
+        );
+        digits + separator_cells
     } else {
         0
     };
 
+    let blame_cells = gutter
+        .iter()
+        .filter_map(|row| row.blame.as_deref())
+        .map(UnicodeWidthStr::width)
+        .max()
+        .map(|width| width + 2)
+        .unwrap_or(0);
+
     let mut max_cells = 0usize;
     for line in lines {
-        let width = line_width_cells(line);
+        let width = line_width_cells(line, tab_width);
         max_cells = max_cells.max(width);
     }
-    max_cells += line_number_cells;
+    if config.whitespace.visible {
+        max_cells += 1;
+    }
+    max_cells += blame_cells + line_number_cells;
 
     let content_width = max_cells as f32 * char_width;
     let content_height = line_count as f32 * line_height_px;
 
     let mut terminal_width = content_width + pad_left + pad_right;
     let mut terminal_height = content_height + pad_top + pad_bottom;
+
+    // Captions, the footer and the watermark live in the margins, which
+    // grow to fit them.
+    let text_width =
+        |text: &str, size: f32| text_width_cells(text) as f32 * size * font.metrics.advance;
+    let annotations = annotation_rows(config, &font_variants.default);
+    let watermark = watermark_svg(config, &font_variants.default, font.metrics.advance);
+    let annotations_width = annotations
+        .iter()
+        .map(|row| text_width(&row.text, row.size))
+        .fold(0.0, f32::max);
+    let rows_height = annotations
+        .iter()
+        .map(|row| row.size * config.line_height)
+        .sum::<f32>();
+    if !annotations.is_empty() {
+        margin_bottom = margin_bottom.max(rows_height + 2.0 * ANNOTATION_GAP);
+        if config.width <= 0.0 {
+            let extra = (annotations_width + 2.0 * ANNOTATION_GAP - terminal_width).max(0.0) / 2.0;
+            margin_left = margin_left.max(extra);
+            margin_right = margin_right.max(extra);
+        }
+    }
+    if let Some(watermark) = &watermark {
+        let needed = watermark.height + 2.0 * ANNOTATION_GAP;
+        match config.watermark.position {
+            WatermarkPosition::TopLeft | WatermarkPosition::TopRight => {
+                margin_top = margin_top.max(needed);
+            }
+            // Below the caption and footer rows, so they never overlap.
+            WatermarkPosition::BottomLeft | WatermarkPosition::BottomRight => {
+                let rows = if annotations.is_empty() {
+                    0.0
+                } else {
+                    rows_height + ANNOTATION_GAP
+                };
+                margin_bottom = margin_bottom.max(rows + needed);
+            }
+        }
+    }
     let mut image_width = terminal_width + margin_left + margin_right;
     let mut image_height = terminal_height + margin_top + margin_bottom;
 
@@ -218,7 +756,9 @@ pub(crate) fn build_svg(
     }
 
     if config.shadow.blur > 0.0 || config.shadow.x != 0.0 || config.shadow.y != 0.0 {
-        svg.push_str(r#"<defs><filter id="shadow" filterUnits="userSpaceOnUse">"#);
+        svg.push_str(&format!(
+            r#"<defs><filter id="{ids}shadow" filterUnits="userSpaceOnUse">"#
+        ));
         svg.push_str(&format!(
             r#"<feGaussianBlur in="SourceAlpha" stdDeviation="{:.2}"/>"#,
             config.shadow.blur
@@ -231,15 +771,24 @@ pub(crate) fn build_svg(
         svg.push_str("</filter></defs>");
     }
 
+    svg.push_str(&backdrop_svg(
+        &config.backdrop,
+        image_width,
+        image_height,
+        ids,
+    ));
+
     let terminal_x = margin_left;
     let terminal_y = margin_top;
-    let mut terminal_attrs = String::new();
-    if config.border.radius > 0.0 {
-        terminal_attrs.push_str(&format!(
+    let terminal_radius = if config.border.radius > 0.0 {
+        format!(
             r#" rx="{:.2}" ry="{:.2}""#,
             config.border.radius, config.border.radius
-        ));
-    }
+        )
+    } else {
+        String::new()
+    };
+    let mut terminal_attrs = terminal_radius.clone();
     if config.border.width > 0.0 {
         terminal_attrs.push_str(&format!(
             r#" stroke="{}" stroke-width="{:.2}""#,
@@ -247,7 +796,7 @@ pub(crate) fn build_svg(
         ));
     }
     if config.shadow.blur > 0.0 || config.shadow.x != 0.0 || config.shadow.y != 0.0 {
-        terminal_attrs.push_str(r#" filter="url(#shadow)""#);
+        terminal_attrs.push_str(&format!(r#" filter="url(#{ids}shadow)""#));
     }
 
     let border_inset = config.border.width / 2.0;
@@ -257,54 +806,154 @@ pub(crate) fn build_svg(
         terminal_y + border_inset,
         (terminal_width - config.border.width).max(0.0),
         (terminal_height - config.border.width).max(0.0),
-        config.background,
+        escape_attr(&colors.background),
         terminal_attrs
     ));
 
+    let gutter_cells = blame_cells + line_number_cells;
+    if gutter_cells > 0 {
+        let gutter_top = terminal_y + bar_height;
+        let gutter_bottom = terminal_y + terminal_height - border_inset;
+        let gutter_right = terminal_x
+            + pad_left
+            + (gutter_cells as f32 - separator_cells as f32 / 2.0) * char_width;
+        if let Some(background) = &colors.gutter_background {
+            svg.push_str(&format!(
+                r#"<defs><clipPath id="{}terminalClip"><rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}"{}/></clipPath></defs>"#,
+                ids,
+                terminal_x + border_inset,
+                terminal_y + border_inset,
+                (terminal_width - config.border.width).max(0.0),
+                (terminal_height - config.border.width).max(0.0),
+                terminal_radius
+            ));
+            svg.push_str(&format!(
+                r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" clip-path="url(#{}terminalClip)"/>"#,
+                terminal_x + border_inset,
+                gutter_top,
+                (gutter_right - terminal_x - border_inset).max(0.0),
+                (gutter_bottom - gutter_top).max(0.0),
+                escape_attr(background),
+                ids
+            ));
+        }
+        if config.gutter.separator_line {
+            svg.push_str(&format!(
+                r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="1"/>"#,
+                gutter_right,
+                gutter_top,
+                gutter_right,
+                gutter_bottom,
+                escape_attr(&colors.line_number)
+            ));
+        }
+    }
+
+    let content_origin = (terminal_x + pad_left, terminal_y + pad_top);
     svg.push_str(&format!(
-        r#"<defs><clipPath id="contentClip"><rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}"/></clipPath></defs>"#,
-        terminal_x + pad_left,
-        terminal_y + pad_top - config.font.size,
+        r#"<defs><clipPath id="{}contentClip"><rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}"/></clipPath></defs>"#,
+        ids,
+        content_origin.0,
+        content_origin.1,
         content_width,
-        (content_height + config.font.size).max(0.0)
+        content_height
     ));
 
     if config.window_controls {
-        let r = 5.5;
-        let x = terminal_x + border_inset + WINDOW_CONTROLS_X_OFFSET;
-        let y = terminal_y + WINDOW_CONTROLS_X_OFFSET;
-        svg.push_str(&format!(
-            r##"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="#FF5A54"/>"##,
-            x, y, r
-        ));
-        svg.push_str(&format!(
-            r##"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="#E6BF29"/>"##,
-            x + WINDOW_CONTROLS_SPACING,
-            y,
-            r
-        ));
-        svg.push_str(&format!(
-            r##"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="#52C12B"/>"##,
-            x + WINDOW_CONTROLS_SPACING * 2.0,
-            y,
-            r
-        ));
+        let center_y = terminal_y
+            + if config.chrome.tabs {
+                bar_height / 2.0
+            } else {
+                WINDOW_CONTROLS_X_OFFSET
+            };
+        let (controls, left_width, right_width) = window_controls_svg(
+            config.chrome.style,
+            &colors,
+            terminal_x + border_inset,
+            terminal_x + terminal_width - border_inset,
+            center_y,
+        );
+        svg.push_str(&controls);
+        let reserved = |width: f32| {
+            if width > 0.0 {
+                border_inset + width + WINDOW_CONTROLS_X_OFFSET
+            } else {
+                WINDOW_CONTROLS_X_OFFSET
+            }
+        };
+        let left_reserved = reserved(left_width);
+        let right_reserved = reserved(right_width);
+        let title_size = title_size(config);
+        let char_width = title_size * font.metrics.advance;
+        let opacity = config.title.opacity.clamp(0.0, 1.0);
 
-        if let Some(title_text) = title_text {
-            let title = sanitize_title_text(title_text);
-            if !title.is_empty() {
-                let title_size = if config.title.size > 0.0 {
-                    config.title.size
+        if config.chrome.tabs {
+            let tabs = title_text
+                .map(sanitize_title_text)
+                .into_iter()
+                .chain(config.chrome.extra_tabs.iter().map(|path| {
+                    sanitize_title_text(&title_text_from_path(
+                        Path::new(path),
+                        config.title.path_style,
+                    ))
+                }))
+                .filter(|tab| !tab.is_empty())
+                .collect::<Vec<_>>();
+            let strip_left = terminal_x + left_reserved;
+            let strip_right = terminal_x + terminal_width - right_reserved;
+            let tab_top = terminal_y + border_inset + (bar_height - border_inset) * 0.2;
+            let tab_bottom = terminal_y + bar_height;
+            let max_tab_width = (strip_right - strip_left) / tabs.len().max(1) as f32;
+            let active = config.chrome.active_tab.min(tabs.len().saturating_sub(1));
+            let mut tab_x = strip_left;
+            for (idx, tab) in tabs.iter().enumerate() {
+                let mut max_cells = ((max_tab_width - 2.0 * WINDOW_TAB_PADDING) / char_width)
+                    .floor()
+                    .max(0.0) as usize;
+                if config.title.max_width > 0 {
+                    max_cells = max_cells.min(config.title.max_width);
+                }
+                let label = truncate_to_cells(tab, max_cells, &config.title.ellipsis);
+                if label.is_empty() {
+                    break;
+                }
+                let tab_width =
+                    text_width_cells(&label) as f32 * char_width + 2.0 * WINDOW_TAB_PADDING;
+                let label_opacity = if idx == active {
+                    svg.push_str(&format!(
+                        r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" rx="6.00" ry="6.00" fill="{}" fill-opacity="0.12"/>"#,
+                        tab_x,
+                        tab_top,
+                        tab_width,
+                        tab_bottom - tab_top,
+                        escape_attr(&colors.title)
+                    ));
+                    opacity
                 } else {
-                    (config.font.size - 2.0).max(8.0)
+                    opacity * 0.6
                 };
-                let char_width = title_size / FONT_HEIGHT_TO_WIDTH_RATIO;
-                let controls_right = x + WINDOW_CONTROLS_SPACING * 2.0 + r;
-                let title_margin = WINDOW_CONTROLS_X_OFFSET;
-                let left_reserved = (controls_right - terminal_x) + title_margin;
-                let right_reserved = title_margin;
+                let opacity_attr = if label_opacity < 1.0 {
+                    format!(r#" fill-opacity="{:.2}""#, label_opacity)
+                } else {
+                    String::new()
+                };
+                svg.push_str(&format!(
+                    r#"<text x="{:.2}" y="{:.2}" fill="{}" font-family="{}" font-size="{:.2}px"{}>{}</text>"#,
+                    tab_x + WINDOW_TAB_PADDING,
+                    (tab_top + tab_bottom) / 2.0 + title_size * 0.35,
+                    escape_attr(&colors.title),
+                    escape_attr(&font_variants.default),
+                    title_size,
+                    opacity_attr,
+                    escape_text(&label)
+                ));
+                tab_x += tab_width;
+            }
+        } else if let Some(title_text) = title_text {
+            let title = sanitize_title_text(title_text);
+            if !title.is_empty() {
                 let available_px = match config.title.align {
-                    TitleAlign::Center => terminal_width - 2.0 * left_reserved,
+                    TitleAlign::Center => terminal_width - 2.0 * left_reserved.max(right_reserved),
                     _ => terminal_width - left_reserved - right_reserved,
                 };
 
@@ -324,8 +973,7 @@ pub(crate) fn build_svg(
                                 (terminal_x + terminal_width - right_reserved, "end")
                             }
                         };
-                        let title_y = terminal_y + WINDOW_CONTROLS_X_OFFSET + (title_size * 0.35);
-                        let opacity = config.title.opacity.clamp(0.0, 1.0);
+                        let title_y = center_y + (title_size * 0.35);
                         let opacity_attr = if opacity < 1.0 {
                             format!(r#" fill-opacity="{:.2}""#, opacity)
                         } else {
@@ -335,7 +983,7 @@ pub(crate) fn build_svg(
                             r#"<text x="{:.2}" y="{:.2}" fill="{}" font-family="{}" font-size="{:.2}px" text-anchor="{}"{}>{}</text>"#,
                             title_x,
                             title_y,
-                            escape_attr(&config.title.color),
+                            escape_attr(&colors.title),
                             escape_attr(&font_variants.default),
                             title_size,
                             anchor,
@@ -349,34 +997,80 @@ pub(crate) fn build_svg(
     }
 
     svg.push_str(&format!(
-        r#"<g font-family="{}" font-size="{:.2}px" clip-path="url(#contentClip)">"#,
+        r#"<g font-family="{}" font-size="{:.2}px" clip-path="url(#{}contentClip)">"#,
         escape_attr(&font_variants.default),
-        config.font.size
+        config.font.size,
+        ids
     ));
     let mut bg_layer = String::new();
+    let mut guide_layer = String::new();
     let mut text_layer = String::new();
+    let mut redact_layer = String::new();
+    let blur_attrs = format!(r#" filter="url(#{ids}redactBlur)" fill-opacity="0.6""#);
 
+    let blame_width_px = blame_cells as f32 * char_width;
     let line_number_width_px = line_number_cells as f32 * char_width;
+    let guide_levels = if config.whitespace.indent_guides {
+        indent_guide_levels(lines, tab_width)
+    } else {
+        Vec::new()
+    };
     for (idx, line) in lines.iter().take(max_visible_lines).enumerate() {
         let line_idx = idx as f32;
-        let y = terminal_y + pad_top + line_height_px * (line_idx + 1.0);
+        let row_top = terminal_y + pad_top + line_height_px * line_idx;
+        let y = row_top + baseline;
         let base_x = terminal_x + pad_left;
 
-        if config.show_line_numbers {
-            let number_text = format!(
-                "{:>width$}  ",
-                idx + 1 + line_offset,
-                width = line_number_cells - 2
-            );
+        let row = gutter.get(idx);
+        if let Some(blame) = row.and_then(|row| row.blame.as_deref()) {
+            if !blame.is_empty() {
+                text_layer.push_str(&format!(
+                    r#"<text x="{:.2}" y="{:.2}" fill="{}" xml:space="preserve">{}</text>"#,
+                    base_x,
+                    y,
+                    escape_attr(&colors.line_number),
+                    escape_text(blame)
+                ));
+            }
+        }
+
+        let number = row.and_then(|row| match row.number {
+            Some(number) => Some(number.to_string()),
+            None if row.continuation => Some(WRAP_CONTINUATION_MARKER.to_string()),
+            None => None,
+        });
+        if let Some(number) = number.filter(|_| config.show_line_numbers) {
+            let digits = line_number_cells - separator_cells;
+            let number_text = match config.gutter.align {
+                GutterAlign::Right => format!("{:>digits$}{}", number, config.gutter.separator),
+                GutterAlign::Left => format!("{:<digits$}{}", number, config.gutter.separator),
+            };
+            let fill = if row.is_some_and(|row| row.focus) {
+                &colors.focus_number
+            } else {
+                &colors.line_number
+            };
             text_layer.push_str(&format!(
-                r##"<text x="{:.2}" y="{:.2}" fill="#777777" xml:space="preserve">{}</text>"##,
-                base_x,
+                r#"<text x="{:.2}" y="{:.2}" fill="{}" xml:space="preserve">{}</text>"#,
+                base_x + blame_width_px,
                 y,
+                escape_attr(fill),
                 escape_text(&number_text)
             ));
         }
 
-        let text_x = base_x + line_number_width_px;
+        let text_x = base_x + blame_width_px + line_number_width_px;
+        if let Some(&(unit, levels)) = guide_levels.get(idx) {
+            for level in 0..levels {
+                guide_layer.push_str(&format!(
+                    r#"<rect x="{:.2}" y="{:.2}" width="1" height="{:.2}" fill="{}"/>"#,
+                    text_x + (level * unit) as f32 * char_width,
+                    row_top,
+                    line_height_px,
+                    escape_attr(&colors.whitespace)
+                ));
+            }
+        }
         text_layer.push_str(&format!(
             r#"<text x="{:.2}" y="{:.2}" fill="{}" xml:space="preserve">"#,
             text_x, y, default_fg
@@ -385,12 +1079,32 @@ pub(crate) fn build_svg(
         let mut cursor_x = text_x;
         for span in &line.spans {
             let text = &span.text;
-            let width_px = span_width_px(text, char_width);
+            let width_px = span_width_px(text, char_width, tab_width);
             if let Some(bg) = &span.style.bg {
-                let rect_y = y - config.font.size;
                 bg_layer.push_str(&format!(
                     r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"/>"#,
-                    cursor_x, rect_y, width_px, line_height_px, bg
+                    cursor_x, row_top, width_px, line_height_px, bg
+                ));
+            }
+
+            if span.style.redacted {
+                let fill = resolve_color(
+                    &config.redact.color,
+                    span.style.fg.as_deref().unwrap_or(default_fg),
+                );
+                let filter = match config.redact.style {
+                    RedactStyle::Solid => "",
+                    RedactStyle::Blur => &blur_attrs,
+                };
+                redact_layer.push_str(&format!(
+                    r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" rx="{:.2}" fill="{}"{}/>"#,
+                    cursor_x,
+                    y - font.metrics.ascent * config.font.size,
+                    width_px,
+                    (font.metrics.ascent + font.metrics.descent) * config.font.size,
+                    config.font.size * 0.15,
+                    escape_attr(&fill),
+                    filter
                 ));
             }
 
@@ -415,27 +1129,203 @@ pub(crate) fn build_svg(
                 attrs.push_str(&format!(r#" text-decoration="{}""#, deco.join(" ")));
             }
 
-            for (group, chunk) in split_text_by_font_group(text) {
-                let mut chunk_attrs = attrs.clone();
-                let family = font_variants.for_group(group);
-                if !family.is_empty() {
-                    chunk_attrs.push_str(&format!(r#" font-family="{}""#, escape_attr(family)));
+            let marker_attrs = format!(r#" fill="{}""#, escape_attr(&colors.whitespace));
+            let runs = if config.whitespace.visible && !span.style.redacted {
+                whitespace_runs(text, span.style.tab)
+            } else {
+                vec![(false, text.clone())]
+            };
+            let mut span_col = 0usize;
+            for (marker, run) in runs {
+                let run_attrs = if marker { &marker_attrs } else { &attrs };
+                for (group, chunk) in split_text_by_font_group(&run) {
+                    let mut chunk_attrs = run_attrs.clone();
+                    let family = font_variants.for_group(group);
+                    if !family.is_empty() {
+                        chunk_attrs.push_str(&format!(r#" font-family="{}""#, escape_attr(family)));
+                    }
+                    // resvg shapes each positioned chunk on its own, so
+                    // placing every cell also keeps ligatures from forming.
+                    if config.font.grid || !config.font.ligatures {
+                        let clusters = cell_clusters(&chunk, span_col, tab_width);
+                        for (text, xs) in grid_runs(&clusters, cursor_x, char_width) {
+                            text_layer.push_str(&format!(
+                                r#"<tspan xml:space="preserve" x="{}"{}>{}</tspan>"#,
+                                xs,
+                                chunk_attrs,
+                                escape_text(&text)
+                            ));
+                        }
+                    } else {
+                        text_layer.push_str(&format!(
+                            r#"<tspan xml:space="preserve"{}>{}</tspan>"#,
+                            chunk_attrs,
+                            escape_text(&chunk)
+                        ));
+                    }
+                    span_col = graphemes(&chunk).fold(span_col, |col, cluster| {
+                        advance_cells(col, cluster, tab_width)
+                    });
                 }
-                text_layer.push_str(&format!(
-                    r#"<tspan xml:space="preserve"{}>{}</tspan>"#,
-                    chunk_attrs,
-                    escape_text(&chunk)
-                ));
             }
             cursor_x += width_px;
         }
+        let elided = row.is_some_and(|row| row.number.is_none() && !row.continuation);
+        let wrapped = gutter.get(idx + 1).is_some_and(|next| next.continuation);
+        if config.whitespace.visible && !elided && !wrapped {
+            text_layer.push_str(&format!(
+                r#"<tspan xml:space="preserve" fill="{}">{}</tspan>"#,
+                escape_attr(&colors.whitespace),
+                WHITESPACE_EOL_MARKER
+            ));
+        }
         text_layer.push_str("</text>");
     }
 
     svg.push_str(&bg_layer);
+    svg.push_str(&guide_layer);
     svg.push_str(&text_layer);
-    svg.push_str("</g></svg>");
-    svg
+    if !redact_layer.is_empty() {
+        if config.redact.style == RedactStyle::Blur {
+            svg.push_str(&format!(
+                r#"<defs><filter id="{}redactBlur" x="-20%" y="-50%" width="140%" height="200%"><feGaussianBlur stdDeviation="{:.2}"/></filter></defs>"#,
+                ids,
+                config.font.size * 0.25
+            ));
+        }
+        svg.push_str(&redact_layer);
+    }
+    svg.push_str("</g>");
+
+    // Rows align to the window edges, or to the widest row when it is
+    // wider than the window.
+    let rows_span = terminal_width.max(annotations_width);
+    let rows_left = terminal_x + (terminal_width - rows_span) / 2.0;
+    let mut row_y = terminal_y + terminal_height + ANNOTATION_GAP;
+    for row in &annotations {
+        let row_height = row.size * config.line_height;
+        let (x, anchor) = match row.align {
+            TitleAlign::Left => (rows_left, "start"),
+            TitleAlign::Center => (rows_left + rows_span / 2.0, "middle"),
+            TitleAlign::Right => (rows_left + rows_span, "end"),
+        };
+        let opacity_attr = if row.opacity < 1.0 {
+            format!(r#" fill-opacity="{:.2}""#, row.opacity)
+        } else {
+            String::new()
+        };
+        svg.push_str(&format!(
+            r#"<text x="{:.2}" y="{:.2}" fill="{}" font-family="{}" font-size="{:.2}px" text-anchor="{}"{}>{}</text>"#,
+            x,
+            row_y + row_height / 2.0 + row.size * 0.35,
+            escape_attr(&row.color),
+            escape_attr(&row.family),
+            row.size,
+            anchor,
+            opacity_attr,
+            escape_text(&row.text)
+        ));
+        row_y += row_height;
+    }
+    if let Some(watermark) = watermark {
+        let x = match config.watermark.position {
+            WatermarkPosition::TopLeft | WatermarkPosition::BottomLeft => ANNOTATION_GAP,
+            WatermarkPosition::TopRight | WatermarkPosition::BottomRight => {
+                image_width - ANNOTATION_GAP - watermark.width
+            }
+        };
+        let y = match config.watermark.position {
+            WatermarkPosition::TopLeft | WatermarkPosition::TopRight => ANNOTATION_GAP,
+            WatermarkPosition::BottomLeft | WatermarkPosition::BottomRight => {
+                image_height - ANNOTATION_GAP - watermark.height
+            }
+        };
+        svg.push_str(&format!(
+            r#"<g transform="translate({:.2} {:.2})" opacity="{:.2}">{}</g>"#,
+            x,
+            y,
+            config.watermark.opacity.clamp(0.0, 1.0),
+            watermark.markup
+        ));
+    }
+    svg.push_str("</svg>");
+    SvgDocument {
+        markup: svg,
+        content_origin,
+    }
+}
+
+/// Groups cell clusters into tspan runs with one `x` per char. A cluster
+/// of several chars gets a run of its own with a single `x`, so it is
+/// shaped as one glyph.
+fn grid_runs(clusters: &[(String, usize)], origin: f32, char_width: f32) -> Vec<(String, String)> {
+    let mut runs: Vec<(String, Vec<String>)> = Vec::new();
+    let mut open = false;
+    for (cluster, col) in clusters {
+        let x = format!("{:.2}", origin + *col as f32 * char_width);
+        let single = cluster.chars().nth(1).is_none();
+        match runs.last_mut() {
+            Some((text, xs)) if open && single => {
+                text.push_str(cluster);
+                xs.push(x);
+            }
+            _ => runs.push((cluster.clone(), vec![x])),
+        }
+        open = single;
+    }
+    runs.into_iter()
+        .map(|(text, xs)| (text, xs.join(" ")))
+        .collect()
+}
+
+/// Splits span text into plain runs and whitespace marker runs. Spaces
+/// become `·`, and the first cell of an expanded tab becomes `→`.
+fn whitespace_runs(text: &str, tab: TabCell) -> Vec<(bool, String)> {
+    let mut runs: Vec<(bool, String)> = Vec::new();
+    for ch in text.chars() {
+        let (marker, ch) = match (ch, tab) {
+            (' ', TabCell::Start) => (true, WHITESPACE_TAB_MARKER),
+            (' ', TabCell::Fill) => (false, ' '),
+            (' ', TabCell::None) => (true, WHITESPACE_SPACE_MARKER),
+            (ch, _) => (false, ch),
+        };
+        match runs.last_mut() {
+            Some((last, run)) if *last == marker => run.push(ch),
+            _ => runs.push((marker, ch.to_string())),
+        }
+    }
+    runs
+}
+
+/// Returns `(unit, levels)` per line for indent guides. The unit is the
+/// smallest indentation in the document; blank lines inherit the shallower
+/// of their neighbours so guides stay continuous.
+pub(crate) fn indent_guide_levels(lines: &[Line], tab_width: usize) -> Vec<(usize, usize)> {
+    let indents = lines
+        .iter()
+        .map(|line| leading_indent_cells(line, tab_width))
+        .collect::<Vec<_>>();
+    let unit = indents
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|indent| *indent > 0)
+        .min()
+        .unwrap_or(tab_width);
+    let mut previous = 0usize;
+    let mut resolved = Vec::with_capacity(indents.len());
+    for (idx, indent) in indents.iter().enumerate() {
+        let indent = match indent {
+            Some(indent) => *indent,
+            None => {
+                let next = indents[idx + 1..].iter().flatten().next().copied();
+                previous.min(next.unwrap_or(0))
+            }
+        };
+        previous = indent;
+        resolved.push((unit, indent / unit));
+    }
+    resolved
 }
 
 fn escape_text(text: &str) -> String {
@@ -444,36 +1334,144 @@ fn escape_text(text: &str) -> String {
         .replace('>', "&gt;")
 }
 
-fn escape_attr(text: &str) -> String {
+pub(crate) fn escape_attr(text: &str) -> String {
     escape_text(text).replace('"', "&quot;")
 }
 
-pub(crate) fn svg_font_face_css(config: &Config) -> Result<Option<String>> {
-    let mut rules = Vec::new();
-    let mut push_rule = |family: &str, data: Vec<u8>, format: &str, mime: &str| {
-        let encoded = base64::engine::general_purpose::STANDARD.encode(data);
-        rules.push(format!(
-            "@font-face {{ font-family: '{}'; src: url(data:{};base64,{}) format('{}'); }}",
-            escape_attr(family),
-            mime,
-            encoded,
-            format
-        ));
+/// Range of the `wght` axis of a variable font.
+fn weight_axis(data: &[u8]) -> Option<(u16, u16)> {
+    let face = ttf_parser::Face::parse(data, 0).ok()?;
+    let axis = face
+        .variation_axes()
+        .into_iter()
+        .find(|axis| axis.tag == ttf_parser::Tag::from_bytes(b"wght"))?;
+    Some((axis.min_value.round() as u16, axis.max_value.round() as u16))
+}
+
+/// CSS rule applying `font.ligatures` and `font.features` to all text.
+/// Features are OpenType tags such as `ss01`, optionally prefixed with
+/// `-` to turn them off or suffixed with `=N` to pick an alternate.
+fn font_feature_rule(font: &Font) -> Result<Option<String>> {
+    let mut settings = Vec::new();
+    if !font.ligatures {
+        settings.push("\"liga\" 0".to_string());
+        settings.push("\"calt\" 0".to_string());
+    }
+    for feature in &font.features {
+        let feature = feature.trim();
+        let (tag, value) = match feature.split_once('=') {
+            Some((tag, value)) => (tag, value.trim().parse::<u16>().ok()),
+            None => match feature.strip_prefix('-') {
+                Some(tag) => (tag, Some(0)),
+                None => (feature.strip_prefix('+').unwrap_or(feature), Some(1)),
+            },
+        };
+        let tag = tag.trim();
+        let valid = tag.len() == 4 && tag.chars().all(|ch| ch.is_ascii_alphanumeric());
+        let Some(value) = value.filter(|_| valid) else {
+            return Err(Error::InvalidInput(format!(
+                "invalid font feature: {feature}"
+            )));
+        };
+        settings.push(format!("\"{tag}\" {value}"));
+    }
+    if settings.is_empty() {
+        return Ok(None);
+    }
+    let ligatures = if font.ligatures {
+        ""
+    } else {
+        " font-variant-ligatures: none;"
     };
-    if let Some(font_file) = &config.font.file {
-        let bytes = std::fs::read(font_file)?;
+    Ok(Some(format!(
+        "text {{{} font-feature-settings: {}; }}",
+        ligatures,
+        settings.join(", ")
+    )))
+}
+
+/// Characters the image draws: the rows, the title and everything the
+/// gutter and whitespace markers can add. Embedded fonts are subset to
+/// these.
+pub(crate) fn svg_text_chars(
+    lines: &[Line],
+    gutter: &[GutterLine],
+    title_text: Option<&str>,
+    config: &Config,
+) -> BTreeSet<char> {
+    let mut chars = BTreeSet::new();
+    for line in lines {
+        for span in &line.spans {
+            chars.extend(span.text.chars());
+        }
+    }
+    for blame in gutter.iter().filter_map(|row| row.blame.as_deref()) {
+        chars.extend(blame.chars());
+    }
+    chars.extend(title_text.unwrap_or_default().chars());
+    chars.extend(config.caption.text.as_deref().unwrap_or_default().chars());
+    chars.extend(footer_text(config).unwrap_or_default().chars());
+    chars.extend(config.watermark.text.as_deref().unwrap_or_default().chars());
+    chars.extend(config.title.ellipsis.chars());
+    chars.extend(config.gutter.separator.chars());
+    chars.extend('0'..='9');
+    chars.extend([
+        ' ',
+        WHITESPACE_SPACE_MARKER,
+        WHITESPACE_TAB_MARKER,
+        WHITESPACE_EOL_MARKER,
+        WRAP_CONTINUATION_MARKER,
+    ]);
+    chars
+}
+
+/// `@font-face` rules embedding the configured font files. With
+/// `font.subset`, each file keeps only the glyphs for `chars`; with
+/// `font.woff2`, sfnt fonts are re-packed as WOFF2.
+pub(crate) fn svg_font_face_css(config: &Config, chars: &BTreeSet<char>) -> Result<Option<String>> {
+    let mut rules = Vec::new();
+    for (font_file, weight, italic) in config.font.faces() {
+        let mut bytes = std::fs::read(font_file)?;
         let ext = Path::new(font_file)
             .extension()
             .and_then(|v| v.to_str())
             .unwrap_or("")
             .to_ascii_lowercase();
-        let (format, mime) = match ext.as_str() {
+        let (mut format, mut mime) = match ext.as_str() {
             "ttf" => ("truetype", "font/ttf"),
             "woff2" => ("woff2", "font/woff2"),
             "woff" => ("woff", "font/woff"),
             _ => ("truetype", "font/ttf"),
         };
-        push_rule(&config.font.family, bytes, format, mime);
+        // A variable font covers its whole weight axis from one file.
+        let weight = match weight_axis(&bytes) {
+            Some((min, max)) => format!("{min} {max}"),
+            None => weight.to_string(),
+        };
+        if config.font.subset {
+            if let Some(subset) = embed_face(&bytes, 0, chars) {
+                bytes = subset;
+            }
+        }
+        if config.font.woff2 {
+            if let Some(woff2) = encode_woff2(&bytes) {
+                bytes = woff2;
+                (format, mime) = ("woff2", "font/woff2");
+            }
+        }
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        rules.push(format!(
+            "@font-face {{ font-family: '{}'; font-weight: {}; font-style: {}; src: url(data:{};base64,{}) format('{}'); }}",
+            escape_attr(&config.font.family),
+            weight,
+            if italic { "italic" } else { "normal" },
+            mime,
+            encoded,
+            format
+        ));
+    }
+    if let Some(rule) = font_feature_rule(&config.font)? {
+        rules.push(rule);
     }
 
     if rules.is_empty() {
@@ -482,3 +1480,73 @@ pub(crate) fn svg_font_face_css(config: &Config) -> Result<Option<String>> {
         Ok(Some(rules.join("")))
     }
 }
+
+/// `@font-face` rules embedding the planned fonts that draw the image's
+/// glyphs, per font group, subset to the characters each one covers. Fonts
+/// already embedded from `font.file` and `font.files` are skipped.
+pub(crate) fn svg_fallback_font_css(
+    lines: &[Line],
+    chars: &BTreeSet<char>,
+    font: &FontPlan,
+    config: &Config,
+    fontdb: &usvg::fontdb::Database,
+) -> Option<String> {
+    let variants = font_family_variants(font, config);
+    let groups = [
+        FontGroup::Default,
+        FontGroup::Cjk,
+        FontGroup::Emoji,
+        FontGroup::Nerd,
+        FontGroup::Unicode,
+    ];
+    let mut group_chars = vec![BTreeSet::new(); groups.len()];
+    let mut row_chars = BTreeSet::new();
+    for line in lines {
+        for span in &line.spans {
+            for (group, text) in split_text_by_font_group(&span.text) {
+                row_chars.extend(text.chars());
+                let slot = groups.iter().position(|item| *item == group).unwrap_or(0);
+                group_chars[slot].extend(text.chars());
+            }
+        }
+    }
+    // Title, gutter and markers are drawn with the default family list.
+    group_chars[0].extend(chars.difference(&row_chars));
+    let families = groups
+        .iter()
+        .zip(group_chars)
+        .filter(|(_, chars)| !chars.is_empty())
+        .map(|(group, chars)| (parse_font_family_list(variants.for_group(*group)), chars))
+        .collect::<Vec<_>>();
+    let skip = if config.font.faces().is_empty() {
+        Vec::new()
+    } else {
+        vec![config.font.family.as_str()]
+    };
+
+    let rules = fallback_faces(fontdb, &families, &skip)
+        .into_iter()
+        .map(|face| {
+            let mut bytes = face.data;
+            let (mut format, mut mime) = ("truetype", "font/ttf");
+            if config.font.woff2 {
+                if let Some(woff2) = encode_woff2(&bytes) {
+                    bytes = woff2;
+                    (format, mime) = ("woff2", "font/woff2");
+                }
+            }
+            format!(
+                "@font-face {{ font-family: '{}'; src: url(data:{};base64,{}) format('{}'); }}",
+                escape_attr(&face.family),
+                mime,
+                base64::engine::general_purpose::STANDARD.encode(bytes),
+                format
+            )
+        })
+        .collect::<Vec<_>>();
+    if rules.is_empty() {
+        None
+    } else {
+        Some(rules.join(""))
+    }
+}
@@ -0,0 +1,230 @@
+use crate::syntax::{scoped_lines, ScopedToken};
+use crate::text::CutResult;
+use crate::{Error, Result};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SymbolKind {
+    Function,
+    Type,
+    Impl,
+    Module,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SymbolQuery {
+    pub(crate) kind: Option<SymbolKind>,
+    pub(crate) path: Vec<String>,
+}
+
+pub(crate) fn parse_symbol_query(input: &str) -> Result<SymbolQuery> {
+    let mut parts = input.split_whitespace().collect::<Vec<_>>();
+    let kind = match parts.first().copied() {
+        Some("fn" | "func" | "function" | "def" | "method") => Some(SymbolKind::Function),
+        Some("struct" | "enum" | "trait" | "class" | "type" | "interface" | "union") => {
+            Some(SymbolKind::Type)
+        }
+        Some("impl") => Some(SymbolKind::Impl),
+        Some("mod" | "module" | "namespace") => Some(SymbolKind::Module),
+        _ => None,
+    };
+    if kind.is_some() {
+        parts.remove(0);
+    }
+    if parts.len() != 1 {
+        return Err(Error::InvalidInput(format!("invalid symbol: {input}")));
+    }
+    let path = parts[0]
+        .split("::")
+        .flat_map(|part| part.split('.'))
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>();
+    if path.is_empty() {
+        return Err(Error::InvalidInput(format!("invalid symbol: {input}")));
+    }
+    Ok(SymbolQuery { kind, path })
+}
+
+pub(crate) fn select_symbol(
+    text: &str,
+    path: Option<&Path>,
+    language: Option<&str>,
+    symbol: &str,
+) -> Result<CutResult> {
+    let query = parse_symbol_query(symbol)?;
+    let tokens = scoped_lines(text, path, language)?;
+    let (start, end) = find_symbol_range(&tokens, &query)
+        .ok_or_else(|| Error::InvalidInput(format!("symbol not found: {symbol}")))?;
+    let lines: Vec<&str> = text.split('\n').collect();
+    Ok(CutResult {
+        text: lines[start..=end].join("\n"),
+        start,
+    })
+}
+
+fn entity_matches(entity: &str, kind: Option<SymbolKind>) -> bool {
+    let Some(kind) = kind else {
+        return true;
+    };
+    let category = entity
+        .strip_prefix("entity.name.")
+        .and_then(|rest| rest.split('.').next())
+        .unwrap_or("");
+    match kind {
+        SymbolKind::Function => matches!(category, "function" | "method"),
+        SymbolKind::Type => matches!(
+            category,
+            "struct" | "enum" | "trait" | "class" | "type" | "union" | "interface"
+        ),
+        SymbolKind::Impl => category == "impl",
+        SymbolKind::Module => matches!(category, "module" | "namespace"),
+    }
+}
+
+pub(crate) fn find_symbol_range(
+    tokens: &[Vec<ScopedToken>],
+    query: &SymbolQuery,
+) -> Option<(usize, usize)> {
+    let range = (0, tokens.len().checked_sub(1)?);
+    find_in_range(tokens, range, &query.path, query.kind)
+}
+
+fn find_in_range(
+    tokens: &[Vec<ScopedToken>],
+    range: (usize, usize),
+    path: &[String],
+    kind: Option<SymbolKind>,
+) -> Option<(usize, usize)> {
+    let (name, rest) = path.split_first()?;
+    let kind = if rest.is_empty() { kind } else { None };
+    definitions(tokens, range, name, kind).find_map(|found| {
+        if rest.is_empty() {
+            Some(found)
+        } else {
+            find_in_range(tokens, found, rest, kind)
+        }
+    })
+}
+
+fn definitions<'a>(
+    tokens: &'a [Vec<ScopedToken>],
+    (from, to): (usize, usize),
+    name: &'a str,
+    kind: Option<SymbolKind>,
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    (from..=to).flat_map(move |line| {
+        let mut column = 0usize;
+        let mut found = Vec::new();
+        for token in &tokens[line] {
+            let is_match = token.text.trim() == name
+                && token
+                    .entity
+                    .as_deref()
+                    .is_some_and(|entity| entity_matches(entity, kind));
+            if is_match {
+                let start = extend_upwards(tokens, line, from);
+                let end = definition_end(tokens, line, column, to);
+                found.push((start, end));
+            }
+            column += token.text.len();
+        }
+        found
+    })
+}
+
+fn masked_code(tokens: &[ScopedToken]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        if token.masked {
+            out.extend(std::iter::repeat_n(' ', token.text.len()));
+        } else {
+            out.push_str(&token.text);
+        }
+    }
+    out
+}
+
+fn is_comment_line(tokens: &[ScopedToken]) -> bool {
+    let has_text = tokens.iter().any(|t| !t.text.trim().is_empty());
+    has_text && masked_code(tokens).trim().is_empty()
+}
+
+fn extend_upwards(tokens: &[Vec<ScopedToken>], line: usize, floor: usize) -> usize {
+    let mut start = line;
+    while start > floor {
+        let prev = &tokens[start - 1];
+        let code = masked_code(prev);
+        let code = code.trim_start();
+        let is_attribute = code.starts_with("#[") || code.starts_with('@');
+        if is_attribute || is_comment_line(prev) {
+            start -= 1;
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+fn indent_of(tokens: &[ScopedToken]) -> Option<usize> {
+    let text = tokens.iter().map(|t| t.text.as_str()).collect::<String>();
+    if text.trim().is_empty() {
+        return None;
+    }
+    Some(text.len() - text.trim_start().len())
+}
+
+fn definition_end(tokens: &[Vec<ScopedToken>], line: usize, column: usize, limit: usize) -> usize {
+    let mut braces = 0i32;
+    let mut parens = 0i32;
+    let mut opened = false;
+    for current in line..=limit {
+        let code = masked_code(&tokens[current]);
+        let skip = if current == line { column } else { 0 };
+        for ch in code.get(skip..).unwrap_or("").chars() {
+            match ch {
+                '(' | '[' => parens += 1,
+                ')' | ']' => parens -= 1,
+                '{' if parens <= 0 => {
+                    braces += 1;
+                    opened = true;
+                }
+                '}' if parens <= 0 => {
+                    braces -= 1;
+                    if opened && braces <= 0 {
+                        return current;
+                    }
+                }
+                ';' if !opened && parens <= 0 => return current,
+                _ => {}
+            }
+        }
+        if !opened && parens <= 0 && code.trim_end().ends_with(':') {
+            return indented_block_end(tokens, line, current, limit);
+        }
+    }
+    limit
+}
+
+fn indented_block_end(
+    tokens: &[Vec<ScopedToken>],
+    line: usize,
+    header_end: usize,
+    limit: usize,
+) -> usize {
+    let base = indent_of(&tokens[line]).unwrap_or(0);
+    let mut end = header_end;
+    for (current, line_tokens) in tokens
+        .iter()
+        .enumerate()
+        .take(limit + 1)
+        .skip(header_end + 1)
+    {
+        match indent_of(line_tokens) {
+            None => continue,
+            Some(indent) if indent > base => end = current,
+            Some(_) => break,
+        }
+    }
+    end
+}
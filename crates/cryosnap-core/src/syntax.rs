@@ -1,53 +1,87 @@
-use crate::{Error, Line, Result, Span, TextStyle};
+use crate::{
+    Error, Line, Result, Span, TextStyle, DEFAULT_BACKGROUND_COLOR, DEFAULT_LINE_NUMBER_COLOR,
+    DEFAULT_TAB_WIDTH, DEFAULT_TITLE_COLOR, WIDE_TAB_SYNTAXES, WIDE_TAB_WIDTH,
+};
 use once_cell::sync::Lazy;
 use std::path::Path;
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Color, FontStyle, ThemeSet};
-use syntect::parsing::SyntaxSet;
+use syntect::highlighting::{Color, FontStyle, Theme, ThemeSet};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
 
-pub(crate) fn highlight_code(
-    text: &str,
-    path: Option<&Path>,
-    language: Option<&str>,
-    theme_name: &str,
-) -> Result<(Vec<Line>, String)> {
-    static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
-    static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
-    static CHARM_THEME: Lazy<syntect::highlighting::Theme> = Lazy::new(charm_theme);
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+static CHARM_THEME: Lazy<Theme> = Lazy::new(charm_theme);
 
-    let ps = &*SYNTAX_SET;
-    let ts = &*THEME_SET;
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ThemeColors {
+    pub(crate) background: String,
+    pub(crate) foreground: String,
+    pub(crate) title: String,
+    pub(crate) gutter: String,
+    pub(crate) gutter_background: String,
+    pub(crate) guide: String,
+}
 
-    let theme = if theme_name.eq_ignore_ascii_case("charm") {
-        &*CHARM_THEME
+fn resolve_theme(theme_name: &str) -> Result<&'static Theme> {
+    let ts = &*THEME_SET;
+    if theme_name.eq_ignore_ascii_case("charm") {
+        Ok(&*CHARM_THEME)
     } else if let Some(theme) = ts.themes.get(theme_name) {
-        theme
+        Ok(theme)
     } else if let Some(theme) = ts.themes.get("base16-ocean.dark") {
-        theme
+        Ok(theme)
     } else if let Some(theme) = ts.themes.values().next() {
-        theme
+        Ok(theme)
     } else {
-        return Err(Error::Render("no themes available".to_string()));
-    };
+        Err(Error::Render("no themes available".to_string()))
+    }
+}
 
-    let syntax = match language {
-        Some(lang) => ps
-            .find_syntax_by_token(lang)
-            .or_else(|| ps.find_syntax_by_extension(lang))
-            .unwrap_or_else(|| ps.find_syntax_plain_text()),
-        None => {
-            if let Some(path) = path {
-                ps.find_syntax_for_file(path)
-                    .ok()
-                    .flatten()
-                    .unwrap_or_else(|| ps.find_syntax_plain_text())
-            } else {
-                let first_line = text.lines().next().unwrap_or("");
-                ps.find_syntax_by_first_line(first_line)
-                    .unwrap_or_else(|| ps.find_syntax_plain_text())
-            }
-        }
+pub(crate) fn theme_colors(theme_name: &str) -> ThemeColors {
+    let settings = resolve_theme(theme_name).ok().map(|theme| &theme.settings);
+    let pick = |color: Option<Color>, fallback: &str| {
+        color
+            .filter(|color| color.a > 0)
+            .map(color_to_hex)
+            .unwrap_or_else(|| fallback.to_string())
+    };
+    let gutter = pick(
+        settings.and_then(|s| s.gutter_foreground),
+        DEFAULT_LINE_NUMBER_COLOR,
+    );
+    let guide = pick(settings.and_then(|s| s.guide), &gutter);
+    let background = pick(
+        settings.and_then(|s| s.background),
+        DEFAULT_BACKGROUND_COLOR,
+    );
+    let foreground = pick(settings.and_then(|s| s.foreground), DEFAULT_TITLE_COLOR);
+    // The built-in theme keeps the title color it had before titles
+    // followed the theme.
+    let title = if theme_name.eq_ignore_ascii_case("charm") {
+        DEFAULT_TITLE_COLOR.to_string()
+    } else {
+        foreground.clone()
     };
+    ThemeColors {
+        gutter_background: pick(settings.and_then(|s| s.gutter), &background),
+        background,
+        foreground,
+        title,
+        gutter,
+        guide,
+    }
+}
+
+pub(crate) fn highlight_code(
+    text: &str,
+    path: Option<&Path>,
+    language: Option<&str>,
+    theme_name: &str,
+) -> Result<(Vec<Line>, String)> {
+    let ps = &*SYNTAX_SET;
+    let theme = resolve_theme(theme_name)?;
+
+    let syntax = find_syntax(text, path, language);
 
     let default_fg = theme.settings.foreground.unwrap_or(Color::WHITE);
     let default_fg_hex = color_to_hex(default_fg);
@@ -104,6 +138,111 @@ pub(crate) fn highlight_code(
     Ok((lines, default_fg_hex))
 }
 
+/// Tab width used when the config does not set one: Go and Makefiles
+/// are conventionally indented with 8-column tabs.
+pub(crate) fn default_tab_width(text: &str, path: Option<&Path>, language: Option<&str>) -> usize {
+    let syntax = find_syntax(text, path, language);
+    if WIDE_TAB_SYNTAXES.contains(&syntax.name.as_str()) {
+        WIDE_TAB_WIDTH
+    } else {
+        DEFAULT_TAB_WIDTH
+    }
+}
+
+fn find_syntax(
+    text: &str,
+    path: Option<&Path>,
+    language: Option<&str>,
+) -> &'static SyntaxReference {
+    let ps = &*SYNTAX_SET;
+    match language {
+        Some(lang) => ps
+            .find_syntax_by_token(lang)
+            .or_else(|| ps.find_syntax_by_extension(lang))
+            .unwrap_or_else(|| ps.find_syntax_plain_text()),
+        None => {
+            if let Some(path) = path {
+                ps.find_syntax_for_file(path)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| ps.find_syntax_plain_text())
+            } else {
+                let first_line = text.lines().next().unwrap_or("");
+                ps.find_syntax_by_first_line(first_line)
+                    .unwrap_or_else(|| ps.find_syntax_plain_text())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ScopedToken {
+    pub(crate) text: String,
+    pub(crate) masked: bool,
+    pub(crate) entity: Option<String>,
+}
+
+pub(crate) fn scoped_lines(
+    text: &str,
+    path: Option<&Path>,
+    language: Option<&str>,
+) -> Result<Vec<Vec<ScopedToken>>> {
+    let ps = &*SYNTAX_SET;
+    let syntax = find_syntax(text, path, language);
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut out = Vec::new();
+
+    let raw_lines: Vec<&str> = text.split('\n').collect();
+    for (idx, raw) in raw_lines.iter().enumerate() {
+        let mut line_with_end = raw.to_string();
+        if idx + 1 < raw_lines.len() {
+            line_with_end.push('\n');
+        }
+        let ops = state
+            .parse_line(&line_with_end, ps)
+            .map_err(|err| Error::Render(format!("parse: {err}")))?;
+        let mut tokens = Vec::new();
+        let mut push_token = |stack: &ScopeStack, piece: &str| {
+            let piece = piece.trim_end_matches(['\n', '\r']);
+            if piece.is_empty() {
+                return;
+            }
+            let mut masked = false;
+            let mut entity = None;
+            for scope in stack.as_slice() {
+                let name = scope.build_string();
+                if name.starts_with("string") || name.starts_with("comment") {
+                    masked = true;
+                }
+                if name.starts_with("entity.name.") {
+                    entity = Some(name);
+                }
+            }
+            tokens.push(ScopedToken {
+                text: piece.to_string(),
+                masked,
+                entity,
+            });
+        };
+        let mut last = 0;
+        for (pos, op) in ops {
+            if pos > last {
+                push_token(&stack, &line_with_end[last..pos]);
+                last = pos;
+            }
+            stack
+                .apply(&op)
+                .map_err(|err| Error::Render(format!("parse: {err}")))?;
+        }
+        if last < line_with_end.len() {
+            push_token(&stack, &line_with_end[last..]);
+        }
+        out.push(tokens);
+    }
+    Ok(out)
+}
+
 fn push_span(spans: &mut Vec<Span>, text: String, style: TextStyle) {
     if let Some(last) = spans.last_mut() {
         if last.style == style {
@@ -118,11 +257,9 @@ fn color_to_hex(color: syntect::highlighting::Color) -> String {
     format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
 }
 
-fn charm_theme() -> syntect::highlighting::Theme {
+fn charm_theme() -> Theme {
     use std::str::FromStr;
-    use syntect::highlighting::{
-        Color, FontStyle, ScopeSelectors, Theme, ThemeItem, ThemeSettings,
-    };
+    use syntect::highlighting::{ScopeSelectors, ThemeItem, ThemeSettings};
 
     let mut theme = Theme {
         name: Some("charm".to_string()),
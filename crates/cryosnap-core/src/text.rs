@@ -1,3 +1,7 @@
+use crate::layout::{grapheme_width, graphemes, tab_stop_cells};
+use crate::{Error, Line, LineBound, LineRange, LineSelection, Result, Span, TabCell, TextStyle};
+use regex::Regex;
+
 #[derive(Debug, Clone)]
 pub(crate) struct CutResult {
     pub(crate) text: String,
@@ -71,58 +75,465 @@ pub(crate) fn cut_text(text: &str, window: &[i32]) -> CutResult {
     }
 }
 
+pub(crate) fn select_lines(text: &str, selection: &LineSelection) -> Result<Vec<CutResult>> {
+    let ranges = match selection {
+        LineSelection::Window(window) => return Ok(vec![cut_text(text, window)]),
+        LineSelection::Ranges(ranges) if ranges.is_empty() => {
+            return Ok(vec![cut_text(text, &[])]);
+        }
+        LineSelection::Ranges(ranges) => ranges,
+    };
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut resolved = ranges
+        .iter()
+        .map(|range| resolve_line_range(&lines, range))
+        .collect::<Result<Vec<_>>>()?;
+    resolved.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in resolved {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    Ok(merged
+        .into_iter()
+        .map(|(start, end)| CutResult {
+            text: lines[start..=end].join("\n"),
+            start,
+        })
+        .collect())
+}
+
+fn resolve_line_range(lines: &[&str], range: &LineRange) -> Result<(usize, usize)> {
+    let last = lines.len() - 1;
+    let start = match &range.start {
+        Some(bound) => resolve_line_bound(lines, bound, 0)?,
+        None => 0,
+    };
+    let end = match &range.end {
+        Some(_) if range.end == range.start => start,
+        Some(bound) => resolve_line_bound(lines, bound, (start + 1).min(last))?,
+        None => last,
+    };
+    Ok((start, end.max(start)))
+}
+
+fn resolve_line_bound(lines: &[&str], bound: &LineBound, from: usize) -> Result<usize> {
+    let last = lines.len() as i64 - 1;
+    match bound {
+        LineBound::Number(n) => {
+            let idx = if *n < 0 {
+                lines.len() as i64 + *n as i64
+            } else {
+                *n as i64 - 1
+            };
+            Ok(idx.clamp(0, last) as usize)
+        }
+        LineBound::Pattern(pattern) => {
+            let re = Regex::new(pattern)
+                .map_err(|err| Error::InvalidInput(format!("invalid line pattern: {err}")))?;
+            lines
+                .iter()
+                .enumerate()
+                .skip(from)
+                .find(|(_, line)| re.is_match(line.trim_end_matches('\r')))
+                .map(|(idx, _)| idx)
+                .ok_or_else(|| Error::InvalidInput(format!("no line matches /{pattern}/")))
+        }
+    }
+}
+
 pub(crate) fn detab(text: &str, tab_width: usize) -> String {
     let mut out = String::new();
     let mut col = 0usize;
-    for ch in text.chars() {
-        if ch == '\t' {
-            let mut count = 0;
-            while !(col + count).is_multiple_of(tab_width) {
-                count += 1;
-            }
-            if count == 0 {
-                count = tab_width;
-            }
+    for cluster in graphemes(text) {
+        if cluster == "\t" {
+            let count = tab_stop_cells(col, tab_width);
             for _ in 0..count {
                 out.push(' ');
             }
             col += count;
         } else {
-            if ch == '\n' {
+            if cluster.contains('\n') {
                 col = 0;
             } else {
-                col += unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
+                col += grapheme_width(cluster);
             }
-            out.push(ch);
+            out.push_str(cluster);
         }
     }
     out
 }
 
-pub(crate) fn wrap_text(text: &str, width: usize) -> String {
+fn leading_spaces(line: &str) -> usize {
+    line.chars().take_while(|ch| *ch == ' ').count()
+}
+
+/// Smallest indentation of the non-blank lines in `text`, in spaces.
+pub(crate) fn common_indent(text: &str) -> Option<usize> {
+    text.split('\n')
+        .filter(|line| !line.trim().is_empty())
+        .map(leading_spaces)
+        .min()
+}
+
+/// Removes up to `remove` leading spaces from every line and indents
+/// non-blank lines by `indent` spaces.
+pub(crate) fn reindent_text(text: &str, remove: usize, indent: usize) -> String {
+    text.split('\n')
+        .map(|line| {
+            let stripped = &line[leading_spaces(line).min(remove)..];
+            if stripped.trim().is_empty() {
+                stripped.to_string()
+            } else {
+                format!("{}{}", " ".repeat(indent), stripped)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Moves per-line cell ranges of `text` to where they land after
+/// [`reindent_text`]; ranges inside the removed indentation are dropped.
+pub(crate) fn reindent_cells(
+    text: &str,
+    ranges: &[Vec<(usize, usize)>],
+    remove: usize,
+    indent: usize,
+) -> Vec<Vec<(usize, usize)>> {
+    text.split('\n')
+        .zip(ranges)
+        .map(|(line, ranges)| {
+            let removed = leading_spaces(line).min(remove);
+            let added = if line.trim().is_empty() { 0 } else { indent };
+            ranges
+                .iter()
+                .filter(|(_, end)| *end > removed)
+                .map(|(start, end)| {
+                    (
+                        (*start).max(removed) - removed + added,
+                        end - removed + added,
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// [`reindent_text`] for styled lines. Leading cells are whitespace, so
+/// they are removed by cell count; the new indent uses the default style.
+pub(crate) fn reindent_lines(lines: Vec<Line>, remove: usize, indent: usize) -> Vec<Line> {
+    lines
+        .into_iter()
+        .map(|line| {
+            let blank = line.spans.iter().all(|span| span.text.trim().is_empty());
+            let mut out = Line::default();
+            if !blank && indent > 0 {
+                out.spans.push(Span {
+                    text: " ".repeat(indent),
+                    style: TextStyle::default(),
+                });
+            }
+            let mut pending = remove;
+            for mut span in line.spans {
+                if pending > 0 {
+                    let skip = span
+                        .text
+                        .chars()
+                        .take_while(|ch| *ch == ' ')
+                        .count()
+                        .min(pending);
+                    pending = if skip == span.text.len() {
+                        pending - skip
+                    } else {
+                        0
+                    };
+                    span.text.drain(..skip);
+                }
+                if !span.text.is_empty() {
+                    out.spans.push(span);
+                }
+            }
+            out
+        })
+        .collect()
+}
+
+/// One visual row of a wrapped logical line. `start..end` are the source
+/// cells shown on the row, after `indent` cells of hanging indent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WrapRow {
+    pub(crate) line: usize,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) indent: usize,
+    pub(crate) continuation: bool,
+}
+
+fn is_word_char(cluster: &str) -> bool {
+    cluster
+        .chars()
+        .next()
+        .is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+fn is_space(cluster: &str) -> bool {
+    cluster.chars().all(char::is_whitespace)
+}
+
+/// Whether a row may break between `prev` and `next`: never inside a word
+/// and never right before closing punctuation.
+fn is_break(prev: &str, next: &str) -> bool {
+    let inside_word = is_word_char(prev) && is_word_char(next);
+    let closing = matches!(next, ")" | "]" | "}" | "," | ";" | ":");
+    !inside_word && !closing
+}
+
+/// Splits one logical line, given as `(grapheme, cell width)` pairs, into rows
+/// of at most `width` cells. Rows break at the last boundary that fits and
+/// fall back to a hard break inside long words. Continuation rows keep the
+/// line's indentation as a hanging indent when it leaves at least half the
+/// width, and whitespace at a break is dropped.
+///
+/// Returns the hanging indent and the cluster index range of each row.
+pub(crate) fn wrap_line(chars: &[(&str, usize)], width: usize) -> (usize, Vec<(usize, usize)>) {
     if width == 0 {
-        return text.to_string();
-    }
-    let mut out_lines = Vec::new();
-    for line in text.split('\n') {
-        let mut current = String::new();
-        let mut current_width = 0usize;
-        for ch in line.chars() {
-            let w = unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0);
-            if current_width + w > width && !current.is_empty() {
-                out_lines.push(current);
-                current = String::new();
-                current_width = 0;
+        return (0, vec![(0, chars.len())]);
+    }
+    let indent = chars
+        .iter()
+        .take_while(|(cluster, _)| *cluster == " ")
+        .map(|(_, w)| *w)
+        .sum::<usize>();
+    let hanging = if indent * 2 <= width { indent } else { 0 };
+    let mut rows = Vec::new();
+    let mut row_start = 0usize;
+    let mut row_width = 0usize;
+    let mut available = width;
+    let mut boundary = None;
+    let mut idx = 0usize;
+    while idx < chars.len() {
+        let (ch, w) = chars[idx];
+        if idx > row_start && is_break(chars[idx - 1].0, ch) {
+            boundary = Some(idx);
+        }
+        if row_width + w > available && idx > row_start {
+            let mut end = boundary.filter(|b| *b > row_start).unwrap_or(idx);
+            let mut next = end;
+            while next < idx && is_space(chars[next].0) {
+                next += 1;
+            }
+            while end > row_start && is_space(chars[end - 1].0) {
+                end -= 1;
+            }
+            if end == row_start {
+                end = idx;
+                next = idx;
             }
-            current.push(ch);
-            current_width += w;
-            if current_width >= width {
-                out_lines.push(current);
-                current = String::new();
-                current_width = 0;
+            rows.push((row_start, end));
+            row_start = next;
+            available = width - hanging;
+            boundary = None;
+            row_width = chars[row_start..idx].iter().map(|(_, w)| *w).sum();
+            if row_width + w > available && idx > row_start {
+                rows.push((row_start, idx));
+                row_start = idx;
+                row_width = 0;
             }
+            if is_space(chars[idx].0) && row_start == idx {
+                row_start += 1;
+                idx += 1;
+                continue;
+            }
+        }
+        row_width += w;
+        idx += 1;
+    }
+    rows.push((row_start, chars.len()));
+    (hanging, rows)
+}
+
+fn grapheme_cells(line: &str) -> Vec<(&str, usize)> {
+    graphemes(line)
+        .map(|cluster| (cluster, grapheme_width(cluster)))
+        .collect()
+}
+
+/// Computes the visual rows of `text` wrapped at `width` cells; a zero
+/// width yields one row per line.
+pub(crate) fn wrap_rows(text: &str, width: usize) -> Vec<WrapRow> {
+    let mut out = Vec::new();
+    for (line_idx, line) in text.split('\n').enumerate() {
+        let chars = grapheme_cells(line);
+        let (hanging, rows) = wrap_line(&chars, width);
+        let mut offsets = Vec::with_capacity(chars.len() + 1);
+        let mut col = 0usize;
+        offsets.push(0);
+        for (_, w) in &chars {
+            col += w;
+            offsets.push(col);
+        }
+        for (row_idx, (start, end)) in rows.into_iter().enumerate() {
+            out.push(WrapRow {
+                line: line_idx,
+                start: offsets[start],
+                end: offsets[end],
+                indent: if row_idx > 0 { hanging } else { 0 },
+                continuation: row_idx > 0,
+            });
         }
-        out_lines.push(current);
     }
-    out_lines.join("\n")
+    out
+}
+
+/// Cell width of plain text, without tab expansion.
+pub(crate) fn cell_width(text: &str) -> usize {
+    graphemes(text).map(grapheme_width).sum()
+}
+
+/// Cell ranges covered by tabs in each line of `text` once expanded.
+pub(crate) fn tab_cells(text: &str, tab_width: usize) -> Vec<Vec<(usize, usize)>> {
+    text.split('\n')
+        .map(|line| {
+            let mut ranges = Vec::new();
+            let mut col = 0usize;
+            for cluster in graphemes(line) {
+                if cluster == "\t" {
+                    let count = tab_stop_cells(col, tab_width);
+                    ranges.push((col, col + count));
+                    col += count;
+                } else {
+                    col += grapheme_width(cluster);
+                }
+            }
+            ranges
+        })
+        .collect()
+}
+
+/// Flags the cells of expanded tabs so the first cell of each tab can be
+/// drawn as a tab marker.
+pub(crate) fn mark_tab_rows(
+    rows: Vec<Line>,
+    source: &str,
+    tabs: &[Vec<(usize, usize)>],
+    wrap: usize,
+) -> Vec<Line> {
+    let starts = tabs
+        .iter()
+        .map(|line| line.iter().map(|&(start, _)| (start, start + 1)).collect())
+        .collect::<Vec<_>>();
+    let fills = tabs
+        .iter()
+        .map(|line| {
+            line.iter()
+                .filter(|(start, end)| start + 1 < *end)
+                .map(|&(start, end)| (start + 1, end))
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    let rows = mark_wrapped_rows(rows, source, &starts, wrap, false, |style| {
+        style.tab = TabCell::Start
+    });
+    mark_wrapped_rows(rows, source, &fills, wrap, false, |style| {
+        style.tab = TabCell::Fill
+    })
+}
+
+/// Applies per-line cell ranges to the rows produced by wrapping `source`
+/// at `wrap` cells.
+pub(crate) fn mark_wrapped_rows(
+    rows: Vec<Line>,
+    source: &str,
+    ranges: &[Vec<(usize, usize)>],
+    wrap: usize,
+    blank: bool,
+    mark: fn(&mut TextStyle),
+) -> Vec<Line> {
+    let layout = wrap_rows(source, wrap);
+    let mut out = Vec::with_capacity(rows.len());
+    for (row_idx, row) in rows.into_iter().enumerate() {
+        let Some(layout) = layout.get(row_idx) else {
+            out.push(row);
+            continue;
+        };
+        let local = ranges
+            .get(layout.line)
+            .map(|ranges| {
+                ranges
+                    .iter()
+                    .filter(|(start, end)| *end > layout.start && *start < layout.end)
+                    .map(|(start, end)| {
+                        (
+                            (*start).max(layout.start) - layout.start + layout.indent,
+                            (*end).min(layout.end) - layout.start + layout.indent,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        out.push(mark_cells(row, &local, blank, mark));
+    }
+    out
+}
+
+/// Splits spans at the given cell ranges and applies `mark` to the cells
+/// inside them. With `blank`, those cells are also replaced by spaces.
+pub(crate) fn mark_cells(
+    line: Line,
+    ranges: &[(usize, usize)],
+    blank: bool,
+    mark: fn(&mut TextStyle),
+) -> Line {
+    if ranges.is_empty() {
+        return line;
+    }
+    let in_range = |col: usize| {
+        ranges
+            .iter()
+            .any(|(start, end)| col >= *start && col < *end)
+    };
+    let mut spans: Vec<Span> = Vec::new();
+    let mut col = 0usize;
+    for span in line.spans {
+        let mut buf = String::new();
+        let mut buf_marked = None;
+        for cluster in graphemes(&span.text) {
+            let w = grapheme_width(cluster);
+            let marked = in_range(col);
+            if buf_marked.is_some_and(|flag| flag != marked) {
+                push_marked_span(&mut spans, &mut buf, &span.style, buf_marked, mark);
+            }
+            buf_marked = Some(marked);
+            if marked && blank {
+                buf.push_str(&" ".repeat(w));
+            } else {
+                buf.push_str(cluster);
+            }
+            col += w;
+        }
+        push_marked_span(&mut spans, &mut buf, &span.style, buf_marked, mark);
+    }
+    Line { spans }
+}
+
+fn push_marked_span(
+    spans: &mut Vec<Span>,
+    buf: &mut String,
+    style: &TextStyle,
+    marked: Option<bool>,
+    mark: fn(&mut TextStyle),
+) {
+    if buf.is_empty() {
+        return;
+    }
+    let mut style = style.clone();
+    if marked == Some(true) {
+        mark(&mut style);
+    }
+    spans.push(Span {
+        text: std::mem::take(buf),
+        style,
+    });
 }
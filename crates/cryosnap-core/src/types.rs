@@ -8,6 +8,25 @@ pub enum InputSource {
     Text(String),
     File(PathBuf),
     Command(String),
+    /// A file as of a git revision, read from the repository object store.
+    Git {
+        path: PathBuf,
+        rev: String,
+    },
+}
+
+impl InputSource {
+    /// Parses a `path@rev` spec into a [`InputSource::Git`] input.
+    pub fn git(spec: &str) -> Option<Self> {
+        let (path, rev) = spec.rsplit_once('@')?;
+        if path.is_empty() || rev.is_empty() {
+            return None;
+        }
+        Some(InputSource::Git {
+            path: PathBuf::from(path),
+            rev: rev.to_string(),
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -13,12 +13,95 @@ export interface ShadowConfig {
   y?: number;
 }
 
+export interface ChromeConfig {
+  style?: 'macos' | 'windows' | 'gnome' | 'minimal' | 'none';
+  tabs?: boolean;
+  extra_tabs?: string[];
+  extraTabs?: string[];
+  active_tab?: number;
+  activeTab?: number;
+}
+
+export interface BackdropConfig {
+  style?: 'none' | 'solid' | 'linear' | 'radial' | 'image' | 'dots' | 'grid' | 'noise';
+  colors?: string[];
+  angle?: number;
+  image?: string;
+  fit?: 'cover' | 'contain';
+  size?: number;
+}
+
+export interface PaneConfig {
+  input?: string;
+  execute?: string;
+  config?: RenderConfig;
+}
+
+export interface LayoutConfig {
+  direction?: 'row' | 'column' | 'grid';
+  columns?: number;
+  gap?: number;
+  chrome?: 'separate' | 'shared';
+  divider?: string;
+  panes?: PaneConfig[];
+}
+
+export interface CaptionConfig {
+  text?: string;
+  align?: 'left' | 'center' | 'right';
+  family?: string;
+  size?: number;
+  color?: string;
+  opacity?: number;
+}
+
+export interface FooterConfig {
+  author?: string;
+  url?: string;
+  date?: string;
+  separator?: string;
+  align?: 'left' | 'center' | 'right';
+  family?: string;
+  size?: number;
+  color?: string;
+  opacity?: number;
+}
+
+export interface WatermarkConfig {
+  text?: string;
+  image?: string;
+  position?: 'top-left' | 'top-right' | 'bottom-left' | 'bottom-right';
+  family?: string;
+  size?: number;
+  color?: string;
+  opacity?: number;
+}
+
+export interface RunConfig {
+  command?: string;
+  divider?: string;
+}
+
+export interface FontFilesConfig {
+  bold?: string;
+  italic?: string;
+  bold_italic?: string;
+  boldItalic?: string;
+}
+
 export interface FontConfig {
   family?: string;
   file?: string;
   filePath?: string;
+  files?: FontFilesConfig;
   size?: number;
   ligatures?: boolean;
+  grid?: boolean;
+  features?: string[];
+  subset?: boolean;
+  woff2?: boolean;
+  embed_fallbacks?: boolean;
+  embedFallbacks?: boolean;
   fallbacks?: string[];
   system_fallback?: 'auto' | 'always' | 'never';
   systemFallback?: 'auto' | 'always' | 'never';
@@ -63,6 +146,61 @@ export interface TitleConfig {
   ellipsis?: string;
 }
 
+export interface GutterConfig {
+  color?: string;
+  blame?: boolean;
+  start?: number;
+  min_digits?: number;
+  minDigits?: number;
+  separator?: string;
+  separator_line?: boolean;
+  separatorLine?: boolean;
+  background?: string;
+  align?: 'left' | 'right';
+  relative?: boolean;
+  focus?: number;
+  focus_color?: string;
+  focusColor?: string;
+}
+
+export interface RedactConfig {
+  patterns?: string[];
+  builtin?: boolean;
+  entropy?: boolean;
+  entropy_threshold?: number;
+  entropyThreshold?: number;
+  entropy_min_length?: number;
+  entropyMinLength?: number;
+  style?: 'solid' | 'blur';
+  color?: string;
+}
+
+export interface BracketsConfig {
+  rainbow?: boolean;
+  palette?: string[];
+  match_at?: string;
+  matchAt?: string;
+}
+
+export interface DedentConfig {
+  enabled?: boolean;
+  indent?: number;
+}
+
+export interface WhitespaceConfig {
+  visible?: boolean;
+  indent_guides?: boolean;
+  indentGuides?: boolean;
+  color?: string;
+}
+
+export interface BlockConfig {
+  index?: number;
+  label?: string;
+  heading?: string;
+  format?: 'auto' | 'markdown' | 'rst' | 'ipynb';
+}
+
 export interface RenderConfig {
   theme?: string;
   background?: string;
@@ -72,26 +210,45 @@ export interface RenderConfig {
   height?: number;
   window?: boolean;
   windowControls?: boolean;
+  chrome?: ChromeConfig;
   show_line_numbers?: boolean;
   showLineNumbers?: boolean;
   language?: string;
   execute_timeout_ms?: number;
   executeTimeoutMs?: number;
   wrap?: number;
+  tab_width?: number;
+  tabWidth?: number;
   lines?: LinesValue;
+  elision?: string;
+  dedent?: DedentConfig;
+  symbol?: string;
   border?: BorderConfig;
   shadow?: ShadowConfig;
+  backdrop?: BackdropConfig;
+  layout?: LayoutConfig;
+  run?: RunConfig;
+  caption?: CaptionConfig;
+  footer?: FooterConfig;
+  watermark?: WatermarkConfig;
   font?: FontConfig;
   raster?: RasterConfig;
   png?: PngConfig;
   title?: TitleConfig;
+  gutter?: GutterConfig;
+  block?: BlockConfig;
+  redact?: RedactConfig;
+  whitespace?: WhitespaceConfig;
+  brackets?: BracketsConfig;
   line_height?: number;
   lineHeight?: number;
+  text_to_path?: boolean;
+  textToPath?: boolean;
 }
 
 export interface RenderOptions {
   input: string;
-  inputKind?: 'text' | 'file' | 'command';
+  inputKind?: 'text' | 'file' | 'command' | 'git';
   configJson?: string;
   config?: RenderConfig;
   format?: 'svg' | 'png' | 'webp';
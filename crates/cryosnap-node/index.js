@@ -17,6 +17,12 @@ function normalizeConfig(config) {
   if (out.execute_timeout_ms === undefined && out.executeTimeoutMs !== undefined) {
     out.execute_timeout_ms = out.executeTimeoutMs;
   }
+  if (out.tab_width === undefined && out.tabWidth !== undefined) {
+    out.tab_width = out.tabWidth;
+  }
+  if (out.text_to_path === undefined && out.textToPath !== undefined) {
+    out.text_to_path = out.textToPath;
+  }
 
   if (out.font && typeof out.font === 'object') {
     const font = { ...out.font };
@@ -26,9 +32,42 @@ function normalizeConfig(config) {
     if (font.systemFallback !== undefined && font.system_fallback === undefined) {
       font.system_fallback = font.systemFallback;
     }
+    if (font.embedFallbacks !== undefined && font.embed_fallbacks === undefined) {
+      font.embed_fallbacks = font.embedFallbacks;
+    }
+    if (font.files && typeof font.files === 'object') {
+      const files = { ...font.files };
+      if (files.boldItalic !== undefined && files.bold_italic === undefined) {
+        files.bold_italic = files.boldItalic;
+      }
+      font.files = files;
+    }
     out.font = font;
   }
 
+  if (out.chrome && typeof out.chrome === 'object') {
+    const chrome = { ...out.chrome };
+    if (chrome.extraTabs !== undefined && chrome.extra_tabs === undefined) {
+      chrome.extra_tabs = chrome.extraTabs;
+    }
+    if (chrome.activeTab !== undefined && chrome.active_tab === undefined) {
+      chrome.active_tab = chrome.activeTab;
+    }
+    out.chrome = chrome;
+  }
+
+  if (out.layout && typeof out.layout === 'object') {
+    const layout = { ...out.layout };
+    if (Array.isArray(layout.panes)) {
+      layout.panes = layout.panes.map((pane) =>
+        pane && typeof pane === 'object'
+          ? { ...pane, config: normalizeConfig(pane.config) }
+          : pane
+      );
+    }
+    out.layout = layout;
+  }
+
   if (out.png && typeof out.png === 'object') {
     const png = { ...out.png };
     if (png.optimizeLevel !== undefined && png.level === undefined) {
@@ -74,6 +113,47 @@ function normalizeConfig(config) {
     out.title = title;
   }
 
+  if (out.redact && typeof out.redact === 'object') {
+    const redact = { ...out.redact };
+    if (redact.entropyThreshold !== undefined && redact.entropy_threshold === undefined) {
+      redact.entropy_threshold = redact.entropyThreshold;
+    }
+    if (redact.entropyMinLength !== undefined && redact.entropy_min_length === undefined) {
+      redact.entropy_min_length = redact.entropyMinLength;
+    }
+    out.redact = redact;
+  }
+
+  if (out.gutter && typeof out.gutter === 'object') {
+    const gutter = { ...out.gutter };
+    if (gutter.minDigits !== undefined && gutter.min_digits === undefined) {
+      gutter.min_digits = gutter.minDigits;
+    }
+    if (gutter.separatorLine !== undefined && gutter.separator_line === undefined) {
+      gutter.separator_line = gutter.separatorLine;
+    }
+    if (gutter.focusColor !== undefined && gutter.focus_color === undefined) {
+      gutter.focus_color = gutter.focusColor;
+    }
+    out.gutter = gutter;
+  }
+
+  if (out.brackets && typeof out.brackets === 'object') {
+    const brackets = { ...out.brackets };
+    if (brackets.matchAt !== undefined && brackets.match_at === undefined) {
+      brackets.match_at = brackets.matchAt;
+    }
+    out.brackets = brackets;
+  }
+
+  if (out.whitespace && typeof out.whitespace === 'object') {
+    const whitespace = { ...out.whitespace };
+    if (whitespace.indentGuides !== undefined && whitespace.indent_guides === undefined) {
+      whitespace.indent_guides = whitespace.indentGuides;
+    }
+    out.whitespace = whitespace;
+  }
+
   return out;
 }
 
@@ -1,4 +1,4 @@
-use cryosnap_core::{Config, InputSource, OutputFormat, RenderRequest};
+use cryosnap_core::{Config, InputSource, OutputFormat, Pane, RenderRequest};
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use std::collections::VecDeque;
@@ -77,6 +77,9 @@ pub fn render(options: RenderOptions) -> Result<Buffer> {
     let input = match options.input_kind.as_deref() {
         Some("file") => InputSource::File(PathBuf::from(options.input)),
         Some("command") => InputSource::Command(options.input),
+        Some("git") => InputSource::git(&options.input).ok_or_else(|| {
+            Error::new(Status::InvalidArg, "git input must be path@rev".to_string())
+        })?,
         _ => InputSource::Text(options.input),
     };
 
@@ -86,14 +89,19 @@ pub fn render(options: RenderOptions) -> Result<Buffer> {
         _ => OutputFormat::Svg,
     };
 
-    let request = RenderRequest {
-        input,
-        config,
-        format,
-    };
-
-    let result = cryosnap_core::render(&request)
-        .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))?;
+    let result = if config.layout.panes.is_empty() {
+        cryosnap_core::render(&RenderRequest {
+            input,
+            config,
+            format,
+        })
+    } else {
+        std::iter::once(Ok(Pane::new(input)))
+            .chain(config.layout.panes.iter().map(|spec| spec.to_pane()))
+            .collect::<cryosnap_core::Result<Vec<_>>>()
+            .and_then(|panes| cryosnap_core::render_panes(&panes, &config, format))
+    }
+    .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))?;
 
     Ok(Buffer::from(result.bytes))
 }
@@ -163,6 +171,28 @@ mod tests {
         restore_env("CRYOSNAP_FONT_AUTO_DOWNLOAD", prev);
     }
 
+    #[test]
+    fn render_composes_layout_panes() {
+        let _lock = state_lock().lock().expect("lock");
+        let prev = with_auto_download_disabled();
+        let dir = temp_dir("panes");
+        let pane = dir.join("pane.txt");
+        std::fs::write(&pane, "second").expect("write");
+        let config = serde_json::json!({
+            "layout": { "panes": [{ "input": pane.to_string_lossy() }] }
+        });
+        let options = RenderOptions {
+            input: "first".to_string(),
+            input_kind: None,
+            config_json: Some(config.to_string()),
+            format: None,
+        };
+        let out = render(options).expect("render");
+        let svg = String::from_utf8(out.to_vec()).expect("utf8");
+        assert!(svg.contains("pane1-contentClip"));
+        restore_env("CRYOSNAP_FONT_AUTO_DOWNLOAD", prev);
+    }
+
     #[test]
     fn render_png_from_file() {
         let _lock = state_lock().lock().expect("lock");